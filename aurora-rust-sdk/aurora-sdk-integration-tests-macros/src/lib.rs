@@ -0,0 +1,92 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Expr, ExprArray, FnArg, ItemFn, Lit, LitStr};
+
+/// Turns an async function into a test which runs with an `AuroraFixture`.
+///
+/// ```ignore
+/// #[aurora_test(users = ["alice", "bob"])]
+/// async fn test_transfer(fixture: AuroraFixture) {
+///     let alice = fixture.user("alice");
+///     // ...
+/// }
+/// ```
+///
+/// The function may take no arguments, in which case no fixture is created.
+#[proc_macro_attribute]
+pub fn aurora_test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut users: Vec<LitStr> = Vec::new();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("users") {
+            let array: ExprArray = meta.value()?.parse()?;
+            for elem in array.elems {
+                match elem {
+                    Expr::Lit(syn::ExprLit {
+                        lit: Lit::Str(name),
+                        ..
+                    }) => users.push(name),
+                    other => return Err(syn::Error::new(other.span(), "expected a string")),
+                }
+            }
+            Ok(())
+        } else {
+            Err(meta.error("unsupported aurora_test argument, expected `users`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+    let function = parse_macro_input!(item as ItemFn);
+
+    match expand(function, &users) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(function: ItemFn, users: &[LitStr]) -> syn::Result<proc_macro2::TokenStream> {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new(
+            sig.fn_token.span(),
+            "aurora_test functions must be async",
+        ));
+    }
+    let setup = match sig.inputs.len() {
+        0 => quote! {},
+        1 => {
+            let FnArg::Typed(arg) = &sig.inputs[0] else {
+                return Err(syn::Error::new(
+                    sig.inputs.span(),
+                    "unexpected self argument",
+                ));
+            };
+            let (pat, ty) = (&arg.pat, &arg.ty);
+            quote! {
+                let #pat: #ty = ::aurora_sdk_integration_tests::fixture::AuroraFixture::new(&[#(#users),*])
+                    .await
+                    .expect("Failed to set up the Aurora fixture");
+            }
+        }
+        _ => {
+            return Err(syn::Error::new(
+                sig.inputs.span(),
+                "aurora_test functions take at most one argument (the fixture)",
+            ))
+        }
+    };
+    let name = &sig.ident;
+    let output = &sig.output;
+
+    Ok(quote! {
+        #[::aurora_sdk_integration_tests::tokio::test(crate = "::aurora_sdk_integration_tests::tokio")]
+        #(#attrs)*
+        #vis async fn #name() #output {
+            #setup
+            #block
+        }
+    })
+}
@@ -0,0 +1,58 @@
+use near_contract_standards::fungible_token::{
+    metadata::{FungibleTokenMetadata, FungibleTokenMetadataProvider},
+    FungibleToken,
+};
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::U128,
+    near_bindgen, AccountId, PanicOnDefault, PromiseOrValue,
+};
+
+#[near_bindgen]
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+pub struct Contract {
+    name: String,
+    symbol: String,
+    decimals: u8,
+    token: FungibleToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(name: String, symbol: String, decimals: u8) -> Self {
+        Self {
+            name,
+            symbol,
+            decimals,
+            token: FungibleToken::new(b"t".to_vec()),
+        }
+    }
+
+    #[payable]
+    pub fn mint(&mut self, account_id: AccountId, amount: U128) {
+        self.token.internal_deposit(&account_id, amount.into());
+    }
+
+    pub fn burn(&mut self, account_id: AccountId, amount: U128) {
+        self.token.internal_withdraw(&account_id, amount.into());
+    }
+}
+
+near_contract_standards::impl_fungible_token_core!(Contract, token);
+near_contract_standards::impl_fungible_token_storage!(Contract, token);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: "ft-1.0.0".to_string(),
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: self.decimals,
+        }
+    }
+}
@@ -1,7 +1,10 @@
 //! Convenience data-types and functions for deploying/interacting with the OpenZeppelin
 //! ERC-20 contract: https://docs.openzeppelin.com/contracts/4.x/erc20#Presets
 
-use crate::aurora_engine::ContractInput;
+use crate::{
+    aurora_engine::{self, AbiContract, AuroraEngine, CallOutcome, ContractInput},
+    nep141,
+};
 use aurora_engine_types::{types::Address, U256};
 
 pub struct Constructor {
@@ -43,6 +46,16 @@ pub struct ERC20 {
     pub address: Address,
 }
 
+impl AbiContract for ERC20 {
+    fn abi(&self) -> &ethabi::Contract {
+        &self.abi
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+}
+
 impl ERC20 {
     /// Creates the bytes that are used as the input to an EVM transaction for calling the
     /// `mint` function of the ERC-20 contract. This function does not interact with any EVM
@@ -106,6 +119,498 @@ impl ERC20 {
     }
 }
 
+/// The roles of the `ERC20PresetMinterPauser` access control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    DefaultAdmin,
+    Minter,
+    Pauser,
+}
+
+impl Role {
+    /// The `bytes32` identifier of the role used by the contract.
+    pub fn id(self) -> [u8; 32] {
+        match self {
+            Self::DefaultAdmin => [0; 32],
+            Self::Minter => aurora_engine_sdk::keccak(b"MINTER_ROLE").0,
+            Self::Pauser => aurora_engine_sdk::keccak(b"PAUSER_ROLE").0,
+        }
+    }
+}
+
+impl ERC20 {
+    pub fn create_transfer_call_bytes(&self, recipient: Address, amount: U256) -> ContractInput {
+        self.create_call_bytes(
+            "transfer",
+            &[
+                ethabi::Token::Address(recipient.raw()),
+                ethabi::Token::Uint(amount),
+            ],
+        )
+    }
+
+    pub fn create_transfer_from_call_bytes(
+        &self,
+        owner: Address,
+        recipient: Address,
+        amount: U256,
+    ) -> ContractInput {
+        self.create_call_bytes(
+            "transferFrom",
+            &[
+                ethabi::Token::Address(owner.raw()),
+                ethabi::Token::Address(recipient.raw()),
+                ethabi::Token::Uint(amount),
+            ],
+        )
+    }
+
+    pub fn create_burn_call_bytes(&self, amount: U256) -> ContractInput {
+        self.create_call_bytes("burn", &[ethabi::Token::Uint(amount)])
+    }
+
+    pub fn create_burn_from_call_bytes(&self, owner: Address, amount: U256) -> ContractInput {
+        self.create_call_bytes(
+            "burnFrom",
+            &[
+                ethabi::Token::Address(owner.raw()),
+                ethabi::Token::Uint(amount),
+            ],
+        )
+    }
+
+    pub fn create_pause_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("pause", &[])
+    }
+
+    pub fn create_unpause_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("unpause", &[])
+    }
+
+    pub fn create_paused_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("paused", &[])
+    }
+
+    pub fn create_total_supply_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("totalSupply", &[])
+    }
+
+    pub fn create_name_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("name", &[])
+    }
+
+    pub fn create_symbol_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("symbol", &[])
+    }
+
+    pub fn create_decimals_call_bytes(&self) -> ContractInput {
+        self.create_call_bytes("decimals", &[])
+    }
+
+    pub fn create_grant_role_call_bytes(&self, role: Role, account: Address) -> ContractInput {
+        self.create_role_call_bytes("grantRole", role, account)
+    }
+
+    pub fn create_revoke_role_call_bytes(&self, role: Role, account: Address) -> ContractInput {
+        self.create_role_call_bytes("revokeRole", role, account)
+    }
+
+    /// `renounceRole` only succeeds if `account` is the sender of the transaction.
+    pub fn create_renounce_role_call_bytes(&self, role: Role, account: Address) -> ContractInput {
+        self.create_role_call_bytes("renounceRole", role, account)
+    }
+
+    pub fn create_has_role_call_bytes(&self, role: Role, account: Address) -> ContractInput {
+        self.create_role_call_bytes("hasRole", role, account)
+    }
+
+    pub fn create_get_role_member_count_call_bytes(&self, role: Role) -> ContractInput {
+        self.create_call_bytes(
+            "getRoleMemberCount",
+            &[ethabi::Token::FixedBytes(role.id().to_vec())],
+        )
+    }
+
+    /// Creates the input for `withdrawToNear(bytes,uint256)`, which only exists on the ERC-20
+    /// contracts deployed by the engine for bridged NEP-141 tokens (so it is not part of the
+    /// `ERC20PresetMinterPauser` ABI). It burns `amount` from the sender and makes the engine
+    /// transfer the same amount of the NEP-141 token to `recipient` (via the exit-to-near
+    /// precompile).
+    pub fn create_withdraw_to_near_call_bytes(
+        &self,
+        recipient: &str,
+        amount: U256,
+    ) -> ContractInput {
+        #[allow(deprecated)]
+        let function = ethabi::Function {
+            name: "withdrawToNear".into(),
+            inputs: vec![
+                ethabi::Param {
+                    name: "recipient".into(),
+                    kind: ethabi::ParamType::Bytes,
+                    internal_type: None,
+                },
+                ethabi::Param {
+                    name: "amount".into(),
+                    kind: ethabi::ParamType::Uint(256),
+                    internal_type: None,
+                },
+            ],
+            outputs: Vec::new(),
+            constant: None,
+            state_mutability: ethabi::StateMutability::NonPayable,
+        };
+        // Unwrap is safe because the tokens match the parameter types above.
+        let data = function
+            .encode_input(&[
+                ethabi::Token::Bytes(recipient.as_bytes().to_vec()),
+                ethabi::Token::Uint(amount),
+            ])
+            .unwrap();
+        ContractInput(data)
+    }
+
+    fn create_role_call_bytes(
+        &self,
+        function: &str,
+        role: Role,
+        account: Address,
+    ) -> ContractInput {
+        self.create_call_bytes(
+            function,
+            &[
+                ethabi::Token::FixedBytes(role.id().to_vec()),
+                ethabi::Token::Address(account.raw()),
+            ],
+        )
+    }
+
+    fn create_call_bytes(&self, function: &str, args: &[ethabi::Token]) -> ContractInput {
+        // Unwraps are safe because the functions and their argument types are fixed by the
+        // `ERC20PresetMinterPauser` ABI.
+        let data = self
+            .abi
+            .function(function)
+            .unwrap()
+            .encode_input(args)
+            .unwrap();
+        ContractInput(data)
+    }
+
+    /// Decodes the (single) return value of the given function.
+    pub fn decode_output(&self, function: &str, output: &[u8]) -> anyhow::Result<ethabi::Token> {
+        aurora_engine::decode_output(&self.abi, function, output)
+    }
+}
+
+/// Helpers for executing (signed by the engine account, or by the given account for the
+/// `_with` variants) and viewing the functions of an `ERC20PresetMinterPauser` contract.
+/// Failed EVM transactions are returned as an `EvmError`; successful ones return their
+/// `CallOutcome` (e.g. for a `GasReport`).
+impl AuroraEngine {
+    pub async fn erc20_mint(
+        &self,
+        erc20: &ERC20,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_mint_with(self.inner.as_account(), erc20, recipient, amount)
+            .await
+    }
+
+    pub async fn erc20_mint_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_mint_call_bytes(recipient, amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_transfer(
+        &self,
+        erc20: &ERC20,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_transfer_with(self.inner.as_account(), erc20, recipient, amount)
+            .await
+    }
+
+    pub async fn erc20_transfer_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_transfer_call_bytes(recipient, amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_transfer_from(
+        &self,
+        erc20: &ERC20,
+        owner: Address,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_transfer_from_with(self.inner.as_account(), erc20, owner, recipient, amount)
+            .await
+    }
+
+    pub async fn erc20_transfer_from_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        owner: Address,
+        recipient: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_transfer_from_call_bytes(owner, recipient, amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_approve(
+        &self,
+        erc20: &ERC20,
+        spender: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_approve_with(self.inner.as_account(), erc20, spender, amount)
+            .await
+    }
+
+    pub async fn erc20_approve_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        spender: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_approve_call_bytes(spender, amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_burn(&self, erc20: &ERC20, amount: U256) -> anyhow::Result<CallOutcome> {
+        self.erc20_burn_with(self.inner.as_account(), erc20, amount)
+            .await
+    }
+
+    pub async fn erc20_burn_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_burn_call_bytes(amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_burn_from(
+        &self,
+        erc20: &ERC20,
+        owner: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_burn_from_with(self.inner.as_account(), erc20, owner, amount)
+            .await
+    }
+
+    pub async fn erc20_burn_from_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        owner: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_burn_from_call_bytes(owner, amount);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_pause(&self, erc20: &ERC20) -> anyhow::Result<CallOutcome> {
+        self.erc20_pause_with(self.inner.as_account(), erc20).await
+    }
+
+    pub async fn erc20_pause_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_pause_call_bytes();
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_unpause(&self, erc20: &ERC20) -> anyhow::Result<CallOutcome> {
+        self.erc20_unpause_with(self.inner.as_account(), erc20)
+            .await
+    }
+
+    pub async fn erc20_unpause_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_unpause_call_bytes();
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_grant_role(
+        &self,
+        erc20: &ERC20,
+        role: Role,
+        account_address: Address,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_grant_role_with(self.inner.as_account(), erc20, role, account_address)
+            .await
+    }
+
+    pub async fn erc20_grant_role_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        role: Role,
+        account_address: Address,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_grant_role_call_bytes(role, account_address);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    pub async fn erc20_revoke_role(
+        &self,
+        erc20: &ERC20,
+        role: Role,
+        account_address: Address,
+    ) -> anyhow::Result<CallOutcome> {
+        self.erc20_revoke_role_with(self.inner.as_account(), erc20, role, account_address)
+            .await
+    }
+
+    pub async fn erc20_revoke_role_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        role: Role,
+        account_address: Address,
+    ) -> anyhow::Result<CallOutcome> {
+        let input = erc20.create_revoke_role_call_bytes(role, account_address);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    /// Removes the role from the EVM address of the signing account.
+    pub async fn erc20_renounce_role_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        role: Role,
+    ) -> anyhow::Result<CallOutcome> {
+        let address =
+            aurora_engine_sdk::types::near_account_to_evm_address(account.id().as_bytes());
+        let input = erc20.create_renounce_role_call_bytes(role, address);
+        self.abi_call_with(account, erc20, input).await
+    }
+
+    /// Withdraws `amount` of a bridged NEP-141 token from the EVM address of `account` to the
+    /// NEAR account `recipient` (which must be registered with the NEP-141 token). Checks that
+    /// the ERC-20 tokens were burned and the NEP-141 tokens were received.
+    pub async fn erc20_withdraw_to_near_with(
+        &self,
+        account: &workspaces::Account,
+        erc20: &ERC20,
+        recipient: &workspaces::AccountId,
+        amount: u128,
+    ) -> anyhow::Result<CallOutcome> {
+        let nep141_id = self.get_nep141_from_erc20(erc20.address).await?;
+        let sender = aurora_engine_sdk::types::near_account_to_evm_address(account.id().as_bytes());
+        let viewer = self.inner.as_account();
+        let erc20_before = self.erc20_balance_of(erc20, sender).await?;
+        let nep141_before = nep141::ft_balance_of(viewer, &nep141_id, recipient).await?;
+
+        let input = erc20.create_withdraw_to_near_call_bytes(recipient.as_str(), amount.into());
+        let outcome = self.abi_call_with(account, erc20, input).await?;
+
+        let erc20_after = self.erc20_balance_of(erc20, sender).await?;
+        let nep141_after = nep141::ft_balance_of(viewer, &nep141_id, recipient).await?;
+        let burned = erc20_before.checked_sub(erc20_after);
+        let received = nep141_after.checked_sub(nep141_before);
+        if burned != Some(U256::from(amount)) || received != Some(amount) {
+            return Err(anyhow::Error::msg(format!(
+                "Withdrawal of {amount} {nep141_id} to {recipient} failed: the ERC-20 balance \
+                 changed from {erc20_before} to {erc20_after} and the NEP-141 balance from \
+                 {nep141_before} to {nep141_after}"
+            )));
+        }
+        Ok(outcome)
+    }
+
+    pub async fn erc20_total_supply(&self, erc20: &ERC20) -> anyhow::Result<U256> {
+        let input = erc20.create_total_supply_call_bytes();
+        self.abi_view(erc20, "totalSupply", input, ethabi::Token::into_uint)
+            .await
+    }
+
+    pub async fn erc20_allowance(
+        &self,
+        erc20: &ERC20,
+        owner: Address,
+        spender: Address,
+    ) -> anyhow::Result<U256> {
+        let input = erc20.create_allowance_call_bytes(owner, spender);
+        self.abi_view(erc20, "allowance", input, ethabi::Token::into_uint)
+            .await
+    }
+
+    pub async fn erc20_name(&self, erc20: &ERC20) -> anyhow::Result<String> {
+        let input = erc20.create_name_call_bytes();
+        self.abi_view(erc20, "name", input, ethabi::Token::into_string)
+            .await
+    }
+
+    pub async fn erc20_symbol(&self, erc20: &ERC20) -> anyhow::Result<String> {
+        let input = erc20.create_symbol_call_bytes();
+        self.abi_view(erc20, "symbol", input, ethabi::Token::into_string)
+            .await
+    }
+
+    pub async fn erc20_decimals(&self, erc20: &ERC20) -> anyhow::Result<u8> {
+        let input = erc20.create_decimals_call_bytes();
+        let decimals = self
+            .abi_view(erc20, "decimals", input, ethabi::Token::into_uint)
+            .await?;
+        // `decimals` is a `uint8` in the ABI, so the value always fits.
+        Ok(decimals.low_u32() as u8)
+    }
+
+    pub async fn erc20_paused(&self, erc20: &ERC20) -> anyhow::Result<bool> {
+        let input = erc20.create_paused_call_bytes();
+        self.abi_view(erc20, "paused", input, ethabi::Token::into_bool)
+            .await
+    }
+
+    pub async fn erc20_has_role(
+        &self,
+        erc20: &ERC20,
+        role: Role,
+        account_address: Address,
+    ) -> anyhow::Result<bool> {
+        let input = erc20.create_has_role_call_bytes(role, account_address);
+        self.abi_view(erc20, "hasRole", input, ethabi::Token::into_bool)
+            .await
+    }
+
+    pub async fn erc20_get_role_member_count(
+        &self,
+        erc20: &ERC20,
+        role: Role,
+    ) -> anyhow::Result<U256> {
+        let input = erc20.create_get_role_member_count_call_bytes(role);
+        self.abi_view(erc20, "getRoleMemberCount", input, ethabi::Token::into_uint)
+            .await
+    }
+}
+
 pub trait ERC20DeployedAt {
     fn deployed_at(self, address: Address) -> ERC20;
 }
@@ -0,0 +1,164 @@
+//! Typed errors for EVM transactions that did not succeed.
+
+use aurora_engine_types::parameters::engine::TransactionStatus;
+use std::fmt;
+
+/// Indicates an error with signature `Error(String)`. For more information see:
+/// * https://docs.soliditylang.org/en/v0.8.18/control-structures.html#assert-and-require
+/// * https://www.4byte.directory/signatures/?bytes4_signature=0x08c379a0
+pub const SOLIDITY_ERROR_SELECTOR: [u8; 4] = [8, 195, 121, 160];
+
+/// Decodes the reason of an `Error(string)` revert. Other revert data is returned
+/// hex-encoded.
+pub fn parse_evm_revert_message(input: &[u8]) -> String {
+    if input.len() < 4 {
+        return format!("0x{}", hex::encode(input));
+    }
+
+    let decoded = if input[0..4] == SOLIDITY_ERROR_SELECTOR {
+        try_abi_parse_revert_message(input)
+    } else {
+        None
+    };
+
+    decoded.unwrap_or_else(|| format!("0x{}", hex::encode(input)))
+}
+
+fn try_abi_parse_revert_message(input: &[u8]) -> Option<String> {
+    ethabi::decode(&[ethabi::ParamType::String], &input[4..])
+        .ok()?
+        .pop()?
+        .into_string()
+}
+
+/// The reason an EVM transaction executed by the Aurora Engine did not succeed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvmErrorKind {
+    /// The transaction reverted. `message` is the decoded `Error(string)` reason if the
+    /// revert data has that form, otherwise it is the hex encoding of `data`.
+    Revert {
+        message: String,
+        data: Vec<u8>,
+    },
+    OutOfGas,
+    OutOfFund,
+    OutOfOffset,
+    CallTooDeep,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvmError {
+    pub kind: EvmErrorKind,
+    /// EVM gas used by the transaction, if known. It is not known for view calls.
+    pub gas_used: Option<u64>,
+}
+
+impl EvmError {
+    /// Creates an error from a failed transaction status. Returns `None` if the
+    /// status is `Succeed`.
+    pub fn from_status(status: TransactionStatus) -> Option<Self> {
+        let kind = match status {
+            TransactionStatus::Succeed(_) => return None,
+            TransactionStatus::Revert(data) => EvmErrorKind::Revert {
+                message: parse_evm_revert_message(&data),
+                data,
+            },
+            TransactionStatus::OutOfGas => EvmErrorKind::OutOfGas,
+            TransactionStatus::OutOfFund => EvmErrorKind::OutOfFund,
+            TransactionStatus::OutOfOffset => EvmErrorKind::OutOfOffset,
+            TransactionStatus::CallTooDeep => EvmErrorKind::CallTooDeep,
+        };
+        Some(Self {
+            kind,
+            gas_used: None,
+        })
+    }
+
+    /// Records the EVM gas used by the failed transaction.
+    pub fn with_gas_used(self, gas_used: u64) -> Self {
+        Self {
+            gas_used: Some(gas_used),
+            ..self
+        }
+    }
+
+    pub fn is_revert(&self) -> bool {
+        matches!(self.kind, EvmErrorKind::Revert { .. })
+    }
+
+    /// The decoded revert reason, if the transaction reverted.
+    pub fn revert_message(&self) -> Option<&str> {
+        match &self.kind {
+            EvmErrorKind::Revert { message, .. } => Some(message.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The raw revert data, if the transaction reverted.
+    pub fn revert_data(&self) -> Option<&[u8]> {
+        match &self.kind {
+            EvmErrorKind::Revert { data, .. } => Some(data.as_slice()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EvmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            EvmErrorKind::Revert { message, .. } => {
+                write!(f, "EVM transaction reverted: {message}")?
+            }
+            kind => write!(f, "EVM transaction failed: {kind:?}")?,
+        }
+        if let Some(gas_used) = self.gas_used {
+            write!(f, " (gas used: {gas_used})")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for EvmError {}
+
+/// Asserts that `result` is an error caused by an EVM revert with the given reason.
+/// The error can be an `EvmError` or an `anyhow::Error` wrapping one.
+#[track_caller]
+pub fn assert_reverted_with<T, E>(result: Result<T, E>, expected_message: &str)
+where
+    T: fmt::Debug,
+    E: Into<anyhow::Error>,
+{
+    let err: anyhow::Error = match result {
+        Ok(value) => {
+            panic!("Expected revert with {expected_message:?}, but call succeeded: {value:?}")
+        }
+        Err(e) => e.into(),
+    };
+    match err
+        .downcast_ref::<EvmError>()
+        .and_then(EvmError::revert_message)
+    {
+        Some(message) => assert_eq!(message, expected_message, "Unexpected revert reason"),
+        None => panic!("Expected revert with {expected_message:?}, got error: {err:?}"),
+    }
+}
+
+#[test]
+fn test_revert_reason_decoding() {
+    let data = [
+        SOLIDITY_ERROR_SELECTOR.as_slice(),
+        ethabi::encode(&[ethabi::Token::String("Game Over".into())]).as_slice(),
+    ]
+    .concat();
+    let err = EvmError::from_status(TransactionStatus::Revert(data.clone()))
+        .unwrap()
+        .with_gas_used(21_000);
+    assert_eq!(err.revert_message(), Some("Game Over"));
+    assert_eq!(err.revert_data(), Some(data.as_slice()));
+    assert_eq!(
+        err.to_string(),
+        "EVM transaction reverted: Game Over (gas used: 21000)"
+    );
+    assert_reverted_with(Err::<(), _>(anyhow::Error::new(err)), "Game Over");
+    assert!(EvmError::from_status(TransactionStatus::Succeed(Vec::new())).is_none());
+}
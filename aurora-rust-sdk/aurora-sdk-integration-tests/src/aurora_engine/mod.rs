@@ -1,26 +1,42 @@
-use crate::wnear::Wnear;
+use crate::{nep141::Nep141, wnear::Wnear};
 use aurora_engine_types::{
     parameters::engine::{
-        CallArgs, DeployErc20TokenArgs, FunctionCallArgsV2, NewCallArgs, NewCallArgsV2,
-        SubmitResult, TransactionStatus, ViewCallArgs,
+        CallArgs, DeployErc20TokenArgs, FunctionCallArgsV2, GetErc20FromNep141CallArgs,
+        GetStorageAtArgs, NewCallArgs, NewCallArgsV2, SubmitResult, TransactionStatus,
+        ViewCallArgs,
     },
     types::{Address, Wei},
-    U256,
+    H256, U256,
 };
+use near_jsonrpc_client::errors::JsonRpcError;
+use near_jsonrpc_primitives::types::query::RpcQueryError;
+use std::{collections::BTreeSet, sync::Mutex};
 use workspaces::{network::Sandbox, Contract, Worker};
 
 pub mod erc20;
+pub mod error;
+pub mod outcome;
 pub mod repo;
+pub mod xcc;
 
 use erc20::ERC20DeployedAt;
+pub use error::{assert_reverted_with, EvmError};
+pub use outcome::{CallOutcome, MintWnearOutcome, NearGasOutcome};
 
-const AURORA_ACCOUNT_ID: &str = "a.test.near";
+const ENGINE_PARENT_ACCOUNT: &str = "test.near";
 const TESTNET_CHAIN_ID: u64 = 1313161555;
 
 /// Newtype for bytes that are meant to be used as the input for an EVM contract.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ContractInput(pub Vec<u8>);
 
+/// An EVM contract with a known ABI (e.g. `erc20::ERC20`), which can be called with
+/// `AuroraEngine::abi_call_with` and viewed with `AuroraEngine::abi_view`.
+pub trait AbiContract {
+    fn abi(&self) -> &ethabi::Contract;
+    fn address(&self) -> Address;
+}
+
 #[derive(Clone)]
 pub struct AuroraEngine {
     pub inner: Contract,
@@ -30,7 +46,16 @@ pub async fn deploy_latest_silo(
     worker: &Worker<Sandbox>,
     account_id: &str,
 ) -> anyhow::Result<AuroraEngine> {
-    let wasm = repo::AuroraEngineRepo::download_and_compile_latest().await?;
+    deploy_latest_silo_with(worker, account_id, &repo::CompileOptions::default()).await
+}
+
+/// Deploys the latest engine compiled with the given options (e.g. testnet features).
+pub async fn deploy_latest_silo_with(
+    worker: &Worker<Sandbox>,
+    account_id: &str,
+    compile_options: &repo::CompileOptions,
+) -> anyhow::Result<AuroraEngine> {
+    let wasm = repo::AuroraEngineRepo::download_and_compile_latest_with(compile_options).await?;
     let (_, sk) = worker.dev_generate().await;
     // We can't use `dev-deploy` here because then the account ID is too long to create
     // `{address}.{engine}` sub-accounts.
@@ -66,11 +91,7 @@ pub async fn deploy_latest_silo(
         .into_result()?;
 
     // Initialize xcc router
-    let router_wasm = repo::AuroraEngineRepo::download()
-        .checkout(repo::LATEST_ENGINE_VERSION)
-        .compile_xcc_router_contract()
-        .execute()
-        .await?;
+    let router_wasm = repo::AuroraEngineRepo::download_and_compile_latest_xcc_router().await?;
     contract
         .call("factory_update")
         .args(router_wasm)
@@ -82,31 +103,146 @@ pub async fn deploy_latest_silo(
     Ok(AuroraEngine { inner: contract })
 }
 
+/// Deploys a new engine to the worker. Each engine deployed this way gets its own short
+/// account ID (`a.test.near`, `b.test.near`, ...), so several engines can coexist in one
+/// worker while the IDs stay short enough for `{address}.{engine}` XCC sub-accounts.
 pub async fn deploy_latest(worker: &Worker<Sandbox>) -> anyhow::Result<AuroraEngine> {
-    deploy_latest_silo(worker, AURORA_ACCOUNT_ID).await
+    let reservation = reserve_engine_account_id(worker).await?;
+    deploy_latest_silo(worker, reservation.account_id()).await
+}
+
+/// Engine account IDs which `deploy_latest` is creating right now, per worker (identified by
+/// its RPC address). An ID is only reserved until the deployment finishes: from then on the
+/// account itself shows that the ID is taken.
+static RESERVED_ENGINE_ACCOUNT_IDS: Mutex<BTreeSet<(String, String)>> = Mutex::new(BTreeSet::new());
+
+/// Releases the reserved engine account ID when dropped.
+struct EngineAccountIdReservation {
+    key: (String, String),
+}
+
+impl EngineAccountIdReservation {
+    fn account_id(&self) -> &str {
+        &self.key.1
+    }
+}
+
+impl Drop for EngineAccountIdReservation {
+    fn drop(&mut self) {
+        // Unwrap is safe because the lock is never held across a panic.
+        RESERVED_ENGINE_ACCOUNT_IDS
+            .lock()
+            .unwrap()
+            .remove(&self.key);
+    }
+}
+
+/// Reserves the first engine account ID which neither exists in the worker nor is reserved
+/// by a concurrent `deploy_latest`.
+async fn reserve_engine_account_id(
+    worker: &Worker<Sandbox>,
+) -> anyhow::Result<EngineAccountIdReservation> {
+    let mut index = 0;
+    loop {
+        let account_id = format!("{}.{ENGINE_PARENT_ACCOUNT}", short_account_name(index));
+        index += 1;
+        let key = (worker.rpc_addr(), account_id);
+        // Unwrap is safe because the lock is never held across a panic.
+        if RESERVED_ENGINE_ACCOUNT_IDS.lock().unwrap().contains(&key) {
+            continue;
+        }
+        // The account may have been created without `deploy_latest`.
+        if account_exists(worker, &key.1.parse()?).await? {
+            continue;
+        }
+        // Another `deploy_latest` may have reserved the ID while the account was viewed.
+        if RESERVED_ENGINE_ACCOUNT_IDS
+            .lock()
+            .unwrap()
+            .insert(key.clone())
+        {
+            return Ok(EngineAccountIdReservation { key });
+        }
+    }
+}
+
+/// Whether the account exists. Only an unknown account error from the RPC means that it
+/// does not, any other error (e.g. the node is not reachable) is returned.
+async fn account_exists(
+    worker: &Worker<Sandbox>,
+    account_id: &workspaces::AccountId,
+) -> anyhow::Result<bool> {
+    let err = match worker.view_account(account_id).await {
+        Ok(_) => return Ok(true),
+        Err(err) => err,
+    };
+    let inner = err.into_inner()?;
+    let is_unknown_account = matches!(
+        inner
+            .downcast_ref::<JsonRpcError<RpcQueryError>>()
+            .and_then(JsonRpcError::handler_error),
+        Some(RpcQueryError::UnknownAccount { .. })
+    );
+    if is_unknown_account {
+        Ok(false)
+    } else {
+        Err(anyhow::anyhow!(inner).context(format!("Failed to view account {account_id}")))
+    }
+}
+
+/// `a`, `b`, ..., `z`, `aa`, `ab`, ...
+fn short_account_name(index: usize) -> String {
+    let mut name = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        name.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    name.reverse();
+    // Unwrap is safe because the name only contains ASCII letters.
+    String::from_utf8(name).unwrap()
 }
 
 impl AuroraEngine {
+    /// Sets the nonce and balance of the address. The engine takes the balance as a `u64`,
+    /// so larger balances are rejected. Returns the NEAR gas burnt by the transaction.
     pub async fn mint_account(
         &self,
         address: Address,
         init_nonce: u64,
         init_balance: Wei,
-    ) -> anyhow::Result<()> {
-        self.inner
+    ) -> anyhow::Result<NearGasOutcome> {
+        if init_balance.raw() > U256::from(u64::MAX) {
+            return Err(anyhow::Error::msg(format!(
+                "Cannot mint {} wei to {}: the balance must fit in a u64",
+                init_balance.raw(),
+                address.encode()
+            )));
+        }
+        let outcome = self
+            .inner
             .call("mint_account")
             .args_borsh((address, init_nonce, init_balance.raw().low_u64()))
             .max_gas()
             .transact()
-            .await?
-            .into_result()?;
-        Ok(())
+            .await?;
+        NearGasOutcome::from_successful_execution(outcome)
     }
 
     pub async fn bridge_nep141(
         &self,
         nep141_id: &workspaces::AccountId,
     ) -> anyhow::Result<erc20::ERC20> {
+        let (erc20, _) = self.bridge_nep141_outcome(nep141_id).await?;
+        Ok(erc20)
+    }
+
+    /// Same as `bridge_nep141`, but also returns the NEAR gas burnt by the transaction.
+    pub async fn bridge_nep141_outcome(
+        &self,
+        nep141_id: &workspaces::AccountId,
+    ) -> anyhow::Result<(erc20::ERC20, NearGasOutcome)> {
         let args = DeployErc20TokenArgs {
             nep141: nep141_id.as_str().parse().unwrap(),
         };
@@ -117,21 +253,84 @@ impl AuroraEngine {
             .max_gas()
             .transact()
             .await?;
+        let near = NearGasOutcome::from_execution(&outcome);
         let address_bytes: Vec<u8> = outcome.borsh()?;
         let address = Address::try_from_slice(&address_bytes).unwrap();
         let erc20 = erc20::Constructor::load().await?.deployed_at(address);
+        Ok((erc20, near))
+    }
+
+    /// Bridges `amount` of the NEP-141 token (which must have been bridged with
+    /// `bridge_nep141`) from `from_account` to `recipient_address` in the EVM. The tokens are
+    /// sent with `ft_transfer_call` to the engine, using the hex-encoded recipient address as
+    /// the message. The engine is registered with the token if needed. Returns the bridged
+    /// ERC-20 token after checking that the recipient's balance increased by `amount`.
+    pub async fn deposit_nep141(
+        &self,
+        token: &Nep141,
+        from_account: &workspaces::Account,
+        recipient_address: Address,
+        amount: u128,
+    ) -> anyhow::Result<erc20::ERC20> {
+        let (erc20, _) = self
+            .deposit_nep141_outcome(token, from_account, recipient_address, amount)
+            .await?;
         Ok(erc20)
     }
 
+    /// Same as `deposit_nep141`, but also returns the NEAR gas burnt by the `ft_transfer_call`
+    /// (not including the registration of the engine with the token).
+    pub async fn deposit_nep141_outcome(
+        &self,
+        token: &Nep141,
+        from_account: &workspaces::Account,
+        recipient_address: Address,
+        amount: u128,
+    ) -> anyhow::Result<(erc20::ERC20, NearGasOutcome)> {
+        let erc20_address = self
+            .get_erc20_from_nep141(token.id())
+            .await
+            .map_err(|e| e.context(format!("{} is not bridged to the engine", token.id())))?;
+        let erc20 = erc20::Constructor::load().await?.deployed_at(erc20_address);
+
+        if token.storage_balance_of(self.inner.id()).await?.is_none() {
+            token
+                .storage_deposit(from_account, Some(self.inner.id()))
+                .await?;
+        }
+        let balance_before = self.erc20_balance_of(&erc20, recipient_address).await?;
+        let (used_amount, near) = token
+            .ft_transfer_call_outcome(
+                from_account,
+                self.inner.id(),
+                amount,
+                &recipient_address.encode(),
+            )
+            .await?;
+        let balance_after = self.erc20_balance_of(&erc20, recipient_address).await?;
+        if used_amount != amount || balance_after != balance_before + U256::from(amount) {
+            return Err(anyhow::Error::msg(format!(
+                "Deposit of {amount} {} failed: the engine used {used_amount} and the ERC-20 \
+                 balance changed from {balance_before} to {balance_after}",
+                token.id()
+            )));
+        }
+
+        Ok((erc20, near))
+    }
+
+    /// Wraps `amount` NEAR of the engine account and mints the same amount of the wNEAR
+    /// ERC-20 token to `dest_address`.
     pub async fn mint_wnear(
         &self,
         wnear: &Wnear,
         dest_address: Address,
         amount: u128,
-    ) -> anyhow::Result<()> {
-        wnear.near_deposit(self.inner.as_account(), amount).await?;
-        let result = self
-            .call_evm_contract(
+    ) -> anyhow::Result<MintWnearOutcome> {
+        let deposit = wnear.near_deposit(self.inner.as_account(), amount).await?;
+        let mint = self
+            .call_evm_contract_with_outcome(
+                self.inner.as_account(),
                 wnear.aurora_token.address,
                 wnear
                     .aurora_token
@@ -139,8 +338,54 @@ impl AuroraEngine {
                 Wei::zero(),
             )
             .await?;
-        unwrap_success(result.status)?;
-        Ok(())
+        mint.output()?;
+        Ok(MintWnearOutcome { deposit, mint })
+    }
+
+    /// Withdraws `amount` of wNEAR from the EVM address of `account` and unwraps it, so that
+    /// `recipient` receives native NEAR. This uses `withdrawToNear` with the recipient suffixed
+    /// by `:unwrap`. Checks that the ERC-20 tokens were burned and that `recipient` received
+    /// `amount` NEAR (less the gas if `recipient` is the signing account).
+    /// Returns the outcome of the `withdrawToNear` transaction.
+    pub async fn unwrap_wnear_with(
+        &self,
+        account: &workspaces::Account,
+        wnear: &Wnear,
+        recipient: &workspaces::Account,
+        amount: u128,
+    ) -> anyhow::Result<CallOutcome> {
+        let erc20 = &wnear.aurora_token;
+        let sender = aurora_engine_sdk::types::near_account_to_evm_address(account.id().as_bytes());
+        let erc20_before = self.erc20_balance_of(erc20, sender).await?;
+        let near_before = recipient.view_account().await?.balance.as_yoctonear();
+
+        let input = erc20.create_withdraw_to_near_call_bytes(
+            &format!("{}:unwrap", recipient.id()),
+            amount.into(),
+        );
+        let outcome = self
+            .call_evm_contract_with_outcome(account, erc20.address, input, Wei::zero())
+            .await?;
+        outcome.output()?;
+
+        let erc20_after = self.erc20_balance_of(erc20, sender).await?;
+        let near_after = recipient.view_account().await?.balance.as_yoctonear();
+        let near_ok = if recipient.id() == account.id() {
+            outcome
+                .near
+                .signer_received(near_before, near_after, amount, 0)
+        } else {
+            near_after.checked_sub(near_before) == Some(amount)
+        };
+        if erc20_before.checked_sub(erc20_after) != Some(U256::from(amount)) || !near_ok {
+            return Err(anyhow::Error::msg(format!(
+                "Unwrapping {amount} wNEAR to {} failed: the ERC-20 balance changed from \
+                 {erc20_before} to {erc20_after} and the NEAR balance from {near_before} to \
+                 {near_after}",
+                recipient.id()
+            )));
+        }
+        Ok(outcome)
     }
 
     pub async fn erc20_balance_of(
@@ -148,16 +393,9 @@ impl AuroraEngine {
         erc20: &erc20::ERC20,
         address: Address,
     ) -> anyhow::Result<U256> {
-        let result = self
-            .view_evm_contract(
-                erc20.address,
-                erc20.create_balance_of_call_bytes(address),
-                None,
-                Wei::zero(),
-            )
-            .await?;
-        let balance = unwrap_success(result).map(|bytes| U256::from_big_endian(&bytes))?;
-        Ok(balance)
+        let input = erc20.create_balance_of_call_bytes(address);
+        self.abi_view(erc20, "balanceOf", input, ethabi::Token::into_uint)
+            .await
     }
 
     pub async fn get_balance(&self, address: Address) -> anyhow::Result<Wei> {
@@ -169,6 +407,120 @@ impl AuroraEngine {
         Ok(Wei::new(U256::from_big_endian(&outcome.result)))
     }
 
+    pub async fn get_nonce(&self, address: Address) -> anyhow::Result<U256> {
+        let outcome = self
+            .inner
+            .view("get_nonce")
+            .args(address.as_bytes().to_vec())
+            .await?;
+        Ok(U256::from_big_endian(&outcome.result))
+    }
+
+    pub async fn get_code(&self, address: Address) -> anyhow::Result<Vec<u8>> {
+        let outcome = self
+            .inner
+            .view("get_code")
+            .args(address.as_bytes().to_vec())
+            .await?;
+        Ok(outcome.result)
+    }
+
+    pub async fn get_storage_at(&self, address: Address, key: H256) -> anyhow::Result<H256> {
+        let args = GetStorageAtArgs {
+            address,
+            key: key.0,
+        };
+        let outcome = self.inner.view("get_storage_at").args_borsh(args).await?;
+        parse_h256(&outcome.result)
+    }
+
+    pub async fn get_chain_id(&self) -> anyhow::Result<u64> {
+        let outcome = self.inner.view("get_chain_id").await?;
+        Ok(U256::from_big_endian(&outcome.result).low_u64())
+    }
+
+    pub async fn get_block_hash(&self, block_height: u64) -> anyhow::Result<H256> {
+        let outcome = self
+            .inner
+            .view("get_block_hash")
+            .args_borsh(block_height)
+            .await?;
+        parse_h256(&outcome.result)
+    }
+
+    pub async fn get_version(&self) -> anyhow::Result<String> {
+        let outcome = self.inner.view("get_version").await?;
+        let version = String::from_utf8(outcome.result)?;
+        Ok(version.trim().into())
+    }
+
+    pub async fn get_owner(&self) -> anyhow::Result<workspaces::AccountId> {
+        let outcome = self.inner.view("get_owner").await?;
+        parse_account_id(outcome.result)
+    }
+
+    pub async fn get_bridge_prover(&self) -> anyhow::Result<workspaces::AccountId> {
+        let outcome = self.inner.view("get_bridge_prover").await?;
+        parse_account_id(outcome.result)
+    }
+
+    pub async fn get_upgrade_index(&self) -> anyhow::Result<u64> {
+        let outcome = self.inner.view("get_upgrade_index").await?;
+        Ok(outcome.borsh()?)
+    }
+
+    pub async fn get_accounts_counter(&self) -> anyhow::Result<u64> {
+        let outcome = self.inner.view("get_accounts_counter").await?;
+        Ok(outcome.borsh()?)
+    }
+
+    /// Returns the bit mask of the precompiles that are currently paused.
+    pub async fn get_paused_precompiles(&self) -> anyhow::Result<u32> {
+        let outcome = self.inner.view("get_paused_precompiles").await?;
+        Ok(outcome.borsh()?)
+    }
+
+    /// Returns the bit mask of the eth-connector functions that are currently paused.
+    pub async fn get_paused_flags(&self) -> anyhow::Result<u8> {
+        let outcome = self.inner.view("get_paused_flags").await?;
+        Ok(outcome.borsh()?)
+    }
+
+    /// Returns the address of the ERC-20 token the given NEP-141 token is bridged to.
+    pub async fn get_erc20_from_nep141(
+        &self,
+        nep141_id: &workspaces::AccountId,
+    ) -> anyhow::Result<Address> {
+        let args = GetErc20FromNep141CallArgs {
+            nep141: nep141_id.as_str().parse().unwrap(),
+        };
+        let outcome = self
+            .inner
+            .view("get_erc20_from_nep141")
+            .args_borsh(args)
+            .await?;
+        Address::try_from_slice(&outcome.result)
+            .map_err(|_| anyhow::Error::msg("Result failed to parse as address"))
+    }
+
+    /// Returns the NEP-141 token the given (engine-deployed) ERC-20 token is bridged from.
+    pub async fn get_nep141_from_erc20(
+        &self,
+        erc20_address: Address,
+    ) -> anyhow::Result<workspaces::AccountId> {
+        let outcome = self
+            .inner
+            .view("get_nep141_from_erc20")
+            .args(erc20_address.as_bytes().to_vec())
+            .await?;
+        parse_account_id(outcome.result)
+    }
+
+    pub async fn factory_get_wnear_address(&self) -> anyhow::Result<Address> {
+        let outcome = self.inner.view("factory_get_wnear_address").await?;
+        Ok(outcome.borsh()?)
+    }
+
     pub async fn deploy_evm_contract(&self, code: Vec<u8>) -> anyhow::Result<Address> {
         self.deploy_evm_contract_with(self.inner.as_account(), code)
             .await
@@ -179,20 +531,33 @@ impl AuroraEngine {
         account: &workspaces::Account,
         code: Vec<u8>,
     ) -> anyhow::Result<Address> {
+        let outcome = self.deploy_evm_contract_with_outcome(account, code).await?;
+        let bytes = outcome.output()?;
+        let address = Address::try_from_slice(&bytes)
+            .map_err(|_| anyhow::Error::msg("Deploy result failed to parse as address"))?;
+        Ok(address)
+    }
+
+    /// Same as `deploy_evm_contract_with`, but returns the full outcome (including the NEAR
+    /// gas burnt) instead of only the address. The deployed address is the output of
+    /// `outcome.result`.
+    pub async fn deploy_evm_contract_with_outcome(
+        &self,
+        account: &workspaces::Account,
+        code: Vec<u8>,
+    ) -> anyhow::Result<CallOutcome> {
         let outcome = account
             .call(self.inner.id(), "deploy_code")
             .args(code)
             .max_gas()
             .transact()
             .await?;
-        let result: SubmitResult = outcome.borsh()?;
-        let address = unwrap_success(result.status).and_then(|bytes| {
-            Address::try_from_slice(&bytes)
-                .map_err(|_| anyhow::Error::msg("Deploy result failed to parse as address"))
-        })?;
-        Ok(address)
+        CallOutcome::from_execution(outcome)
     }
 
+    /// Executes `input` on the contract at `address`, signed by the engine account. An EVM
+    /// transaction which did not succeed (e.g. a revert) is still `Ok`: check its status
+    /// with `unwrap_submit_result`, or use `try_call_evm_contract` to get an `EvmError`.
     pub async fn call_evm_contract(
         &self,
         address: Address,
@@ -203,6 +568,8 @@ impl AuroraEngine {
             .await
     }
 
+    /// Same as `call_evm_contract`, but signed by `account`. A reverted transaction is `Ok`
+    /// as well; only the `try_call_evm_contract*` variants decode reverts into an `EvmError`.
     pub async fn call_evm_contract_with(
         &self,
         account: &workspaces::Account,
@@ -210,6 +577,48 @@ impl AuroraEngine {
         input: ContractInput,
         value: Wei,
     ) -> anyhow::Result<SubmitResult> {
+        let outcome = self
+            .call_evm_contract_with_outcome(account, address, input, value)
+            .await?;
+        Ok(outcome.result)
+    }
+
+    /// Same as `call_evm_contract`, but an EVM transaction which did not succeed is an
+    /// `EvmError` (with the decoded revert reason) instead of `Ok`. Returns the output of
+    /// the transaction.
+    pub async fn try_call_evm_contract(
+        &self,
+        address: Address,
+        input: ContractInput,
+        value: Wei,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.try_call_evm_contract_with(self.inner.as_account(), address, input, value)
+            .await
+    }
+
+    pub async fn try_call_evm_contract_with(
+        &self,
+        account: &workspaces::Account,
+        address: Address,
+        input: ContractInput,
+        value: Wei,
+    ) -> anyhow::Result<Vec<u8>> {
+        let outcome = self
+            .call_evm_contract_with_outcome(account, address, input, value)
+            .await?;
+        Ok(outcome.output()?)
+    }
+
+    /// Same as `call_evm_contract_with`, but also returns the NEAR gas burnt by the
+    /// transaction and all of its receipts. A reverted transaction is `Ok` as well; use
+    /// `CallOutcome::output` to get its `EvmError`.
+    pub async fn call_evm_contract_with_outcome(
+        &self,
+        account: &workspaces::Account,
+        address: Address,
+        input: ContractInput,
+        value: Wei,
+    ) -> anyhow::Result<CallOutcome> {
         let args = CallArgs::V2(FunctionCallArgsV2 {
             contract: address,
             value: value.to_bytes(),
@@ -221,8 +630,7 @@ impl AuroraEngine {
             .max_gas()
             .transact()
             .await?;
-        let result = outcome.borsh()?;
-        Ok(result)
+        CallOutcome::from_execution(outcome)
     }
 
     pub async fn view_evm_contract(
@@ -238,24 +646,90 @@ impl AuroraEngine {
             amount: value.to_bytes(),
             input: input.0,
         };
-        let outcome = self
-            .inner
-            .call("view")
-            .args_borsh(args)
-            .max_gas()
-            .transact()
-            .await?;
+        let outcome = self.inner.view("view").args_borsh(args).await?;
         let result = outcome.borsh()?;
         Ok(result)
     }
+
+    /// Executes `input` on the contract (without attaching value), signed by `account`.
+    /// Failed EVM transactions are returned as an `EvmError`.
+    pub async fn abi_call_with(
+        &self,
+        account: &workspaces::Account,
+        contract: &impl AbiContract,
+        input: ContractInput,
+    ) -> anyhow::Result<CallOutcome> {
+        let outcome = self
+            .call_evm_contract_with_outcome(account, contract.address(), input, Wei::zero())
+            .await?;
+        outcome.output()?;
+        Ok(outcome)
+    }
+
+    /// Views `function` of the contract and converts its (single) return value with
+    /// `into_value`, e.g. `ethabi::Token::into_uint`.
+    pub async fn abi_view<T>(
+        &self,
+        contract: &impl AbiContract,
+        function: &str,
+        input: ContractInput,
+        into_value: fn(ethabi::Token) -> Option<T>,
+    ) -> anyhow::Result<T> {
+        let result = self
+            .view_evm_contract(contract.address(), input, None, Wei::zero())
+            .await?;
+        let output = unwrap_success(result)?;
+        let token = decode_output(contract.abi(), function, &output)?;
+        into_value(token)
+            .ok_or_else(|| anyhow::Error::msg(format!("Unexpected return type of `{function}`")))
+    }
 }
 
-pub fn unwrap_success(status: TransactionStatus) -> anyhow::Result<Vec<u8>> {
+/// Decodes the (single) return value of the given function.
+pub(crate) fn decode_output(
+    abi: &ethabi::Contract,
+    function: &str,
+    output: &[u8],
+) -> anyhow::Result<ethabi::Token> {
+    abi.function(function)?
+        .decode_output(output)?
+        .pop()
+        .ok_or_else(|| anyhow::Error::msg(format!("`{function}` returned no value")))
+}
+
+/// Returns the output of a successful EVM transaction, or an `EvmError` describing why
+/// it failed (including the decoded revert reason).
+pub fn unwrap_success(status: TransactionStatus) -> Result<Vec<u8>, EvmError> {
     match status {
         TransactionStatus::Succeed(bytes) => Ok(bytes),
-        status => Err(anyhow::Error::msg(format!(
-            "Transaction failed: {:?}",
-            status
-        ))),
+        // Unwrap is safe because the status is not `Succeed`.
+        status => Err(EvmError::from_status(status).unwrap()),
     }
 }
+
+/// Same as `unwrap_success`, but the error also records the EVM gas used by the transaction.
+pub fn unwrap_submit_result(result: SubmitResult) -> Result<Vec<u8>, EvmError> {
+    let gas_used = result.gas_used;
+    unwrap_success(result.status).map_err(|e| e.with_gas_used(gas_used))
+}
+
+fn parse_h256(bytes: &[u8]) -> anyhow::Result<H256> {
+    if bytes.len() != 32 {
+        return Err(anyhow::Error::msg("Result failed to parse as H256"));
+    }
+    Ok(H256::from_slice(bytes))
+}
+
+fn parse_account_id(bytes: Vec<u8>) -> anyhow::Result<workspaces::AccountId> {
+    let account_id = String::from_utf8(bytes)?.parse()?;
+    Ok(account_id)
+}
+
+#[test]
+fn test_short_account_name() {
+    let names: Vec<String> = [0, 1, 25, 26, 27, 701, 702]
+        .into_iter()
+        .map(short_account_name)
+        .collect();
+    assert_eq!(names, ["a", "b", "z", "aa", "ab", "zz", "aaa"]);
+}
@@ -0,0 +1,103 @@
+use super::EvmError;
+use aurora_engine_types::parameters::engine::{ResultLog, SubmitResult};
+use workspaces::{
+    result::ExecutionFinalResult,
+    types::{Gas, NearToken},
+    AccountId,
+};
+
+/// Gas burnt by one step (the transaction itself or one of its receipts) of a NEAR transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceiptGas {
+    /// The signer for the transaction, the receiver for receipts.
+    pub executor_id: AccountId,
+    pub gas_burnt: Gas,
+    pub tokens_burnt: NearToken,
+}
+
+/// The NEAR gas accounting of a transaction, including all the receipts it caused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearGasOutcome {
+    pub total_gas_burnt: Gas,
+    pub total_tokens_burnt: NearToken,
+    /// The transaction outcome followed by the receipt outcomes.
+    pub receipts: Vec<ReceiptGas>,
+}
+
+impl NearGasOutcome {
+    pub fn from_execution(outcome: &ExecutionFinalResult) -> Self {
+        let receipts: Vec<ReceiptGas> = outcome
+            .outcomes()
+            .into_iter()
+            .map(|outcome| ReceiptGas {
+                executor_id: outcome.executor_id.clone(),
+                gas_burnt: outcome.gas_burnt,
+                tokens_burnt: outcome.tokens_burnt,
+            })
+            .collect();
+        let total_tokens_burnt = receipts
+            .iter()
+            .map(|receipt| receipt.tokens_burnt.as_yoctonear())
+            .sum();
+        Self {
+            total_gas_burnt: outcome.total_gas_burnt,
+            total_tokens_burnt: NearToken::from_yoctonear(total_tokens_burnt),
+            receipts,
+        }
+    }
+
+    /// Same as `from_execution`, but a failed transaction is an error.
+    pub fn from_successful_execution(outcome: ExecutionFinalResult) -> anyhow::Result<Self> {
+        let near = Self::from_execution(&outcome);
+        outcome.into_result()?;
+        Ok(near)
+    }
+
+    /// Whether the native NEAR balance of the signer, which changed from `before` to `after`
+    /// during this transaction, shows that the signer received `amount`. The signer also
+    /// paid for the gas burnt and the attached `deposit`, so the balance may have increased
+    /// by less.
+    pub fn signer_received(&self, before: u128, after: u128, amount: u128, deposit: u128) -> bool {
+        let paid = self.total_tokens_burnt.as_yoctonear() + deposit;
+        after <= before + amount && after + paid >= before + amount
+    }
+}
+
+/// The result of an EVM transaction submitted to the engine together with the NEAR gas
+/// it cost.
+#[derive(Debug, Clone)]
+pub struct CallOutcome {
+    pub near: NearGasOutcome,
+    pub result: SubmitResult,
+}
+
+impl CallOutcome {
+    /// Parses the `SubmitResult` returned by the engine from the transaction outcome.
+    pub fn from_execution(outcome: ExecutionFinalResult) -> anyhow::Result<Self> {
+        let near = NearGasOutcome::from_execution(&outcome);
+        let result = outcome.borsh()?;
+        Ok(Self { near, result })
+    }
+
+    /// The output of the EVM transaction, or an `EvmError` if it did not succeed.
+    pub fn output(&self) -> Result<Vec<u8>, EvmError> {
+        super::unwrap_submit_result(self.result.clone())
+    }
+
+    pub fn evm_gas_used(&self) -> u64 {
+        self.result.gas_used
+    }
+
+    pub fn logs(&self) -> &[ResultLog] {
+        &self.result.logs
+    }
+}
+
+/// The outcome of `AuroraEngine::mint_wnear`.
+#[derive(Debug, Clone)]
+pub struct MintWnearOutcome {
+    /// The `near_deposit` which wraps the engine's NEAR.
+    pub deposit: NearGasOutcome,
+    /// The EVM transaction which mints the wNEAR ERC-20 tokens.
+    pub mint: CallOutcome,
+}
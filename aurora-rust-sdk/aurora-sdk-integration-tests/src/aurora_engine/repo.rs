@@ -1,15 +1,26 @@
-use crate::utils::{git::Git, process};
+use crate::utils::{
+    git::{self, Git},
+    lock::FileLock,
+    process,
+};
 use std::{
     marker::PhantomData,
     path::{Path, PathBuf},
 };
-use tokio::sync::Mutex;
 
 pub const LATEST_ENGINE_VERSION: &str = "896005e1bb0e3e8cedb6a29e8988d1e556119d99";
 const TARGET: &str = "target";
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
 const ENGINE_PATH: &str = "aurora-engine";
-/// A lock to prevent multiple tests from modifying the aurora-engine repo at the same time.
-static ENGINE_LOCK: Mutex<()> = Mutex::const_new(());
+const ENGINE_REPO_URL: &str = "https://github.com/aurora-is-near/aurora-engine.git";
+/// Path of a local clone or bundle file of the aurora-engine repo to fetch commits from
+/// before falling back to GitHub.
+const GIT_MIRROR_ENV_VAR: &str = "AURORA_ENGINE_GIT_MIRROR";
+/// A lock file to prevent multiple tests (possibly in different processes) from modifying
+/// the aurora-engine repo at the same time.
+const ENGINE_LOCK_FILE: &str = "aurora-engine.lock";
+/// Directory (in the target directory) with the compiled contracts of previous runs.
+const WASM_CACHE_DIR: &str = "aurora-engine-wasm";
 
 pub struct AuroraEngineRepo;
 
@@ -24,13 +35,132 @@ pub enum ActionOutput {
     Bytes(Vec<u8>),
 }
 
+/// How to compile a contract from the aurora-engine repo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOptions {
+    /// Cargo features to enable (default features are always disabled).
+    pub features: Vec<String>,
+    /// Cargo profile, e.g. `release` or `dev`.
+    pub profile: String,
+    /// Extra environment variables for the `cargo` invocation (e.g. `RUSTFLAGS`).
+    pub env: Vec<(String, String)>,
+}
+
+impl CompileOptions {
+    /// The engine as deployed on mainnet, with the test-only methods enabled.
+    pub fn mainnet() -> Self {
+        Self {
+            features: vec!["mainnet".into(), "integration-test".into()],
+            profile: "release".into(),
+            env: Vec::new(),
+        }
+    }
+
+    /// The engine as deployed on testnet, with the test-only methods enabled.
+    pub fn testnet() -> Self {
+        Self {
+            features: vec!["testnet".into(), "integration-test".into()],
+            ..Self::mainnet()
+        }
+    }
+
+    /// Default options for the xcc-router contract.
+    pub fn xcc_router() -> Self {
+        Self {
+            features: Vec::new(),
+            profile: "release".into(),
+            env: vec![("RUSTFLAGS".into(), "-C link-arg=-s".into())],
+        }
+    }
+
+    /// A short identifier of the options. Builds with different options use separate target
+    /// directories named by this identifier, so their artifacts are never confused.
+    pub fn artifact_id(&self) -> String {
+        let mut features = self.features.clone();
+        features.sort();
+        let mut env = self.env.clone();
+        env.sort();
+        let hash = aurora_engine_sdk::keccak(format!("{features:?}{env:?}").as_bytes());
+        format!("{}-{}", self.profile, hex::encode(&hash.as_bytes()[..8]))
+    }
+
+    /// The name of the directory cargo puts the artifacts of the profile into.
+    fn profile_dir(&self) -> &str {
+        match self.profile.as_str() {
+            "dev" | "test" => "debug",
+            "bench" => "release",
+            other => other,
+        }
+    }
+
+    fn cargo_command(&self, toolchain: &str, path: &Path) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("cargo");
+        command
+            .env("RUSTUP_TOOLCHAIN", toolchain)
+            .envs(self.env.iter().map(|(k, v)| (k, v)))
+            .current_dir(path)
+            .args([
+                "build",
+                "--target",
+                WASM_TARGET,
+                "--profile",
+                self.profile.as_str(),
+            ])
+            .arg("--target-dir")
+            .arg(self.target_dir(path));
+        if !self.features.is_empty() {
+            command.arg(format!("--features={}", self.features.join(",")));
+        }
+        command
+    }
+
+    fn target_dir(&self, path: &Path) -> PathBuf {
+        path.join(TARGET).join(self.artifact_id())
+    }
+
+    fn binary_path(&self, path: &Path, binary_name: &str) -> PathBuf {
+        self.target_dir(path)
+            .join(WASM_TARGET)
+            .join(self.profile_dir())
+            .join(binary_name)
+    }
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
 impl AuroraEngineRepo {
     pub async fn download_and_compile_latest() -> anyhow::Result<Vec<u8>> {
-        Self::download()
-            .checkout(LATEST_ENGINE_VERSION)
-            .compile_engine_contract()
-            .execute()
-            .await
+        Self::download_and_compile_latest_with(&CompileOptions::default()).await
+    }
+
+    /// Compiles the engine at `LATEST_ENGINE_VERSION`. The compiled contract is cached, so
+    /// the repo is only downloaded and compiled once per set of options.
+    pub async fn download_and_compile_latest_with(
+        options: &CompileOptions,
+    ) -> anyhow::Result<Vec<u8>> {
+        cached_wasm("aurora_engine", options, || {
+            Self::download()
+                .checkout(LATEST_ENGINE_VERSION)
+                .compile_engine_contract_with(options.clone())
+                .execute()
+        })
+        .await
+    }
+
+    /// Compiles the xcc-router at `LATEST_ENGINE_VERSION`. The compiled contract is cached.
+    pub async fn download_and_compile_latest_xcc_router() -> anyhow::Result<Vec<u8>> {
+        let options = CompileOptions::xcc_router();
+        cached_wasm("xcc_router", &options, || {
+            Self::download()
+                .checkout(LATEST_ENGINE_VERSION)
+                .compile_xcc_router_contract_with(options.clone())
+                .execute()
+        })
+        .await
     }
 
     pub fn download() -> AuroraEngineRepoActions<()> {
@@ -54,8 +184,15 @@ impl<T> AuroraEngineRepoActions<T> {
     }
 
     pub fn compile_engine_contract(self) -> AuroraEngineRepoActions<Vec<u8>> {
+        self.compile_engine_contract_with(CompileOptions::default())
+    }
+
+    pub fn compile_engine_contract_with(
+        self,
+        options: CompileOptions,
+    ) -> AuroraEngineRepoActions<Vec<u8>> {
         let mut current_actions = self.actions;
-        current_actions.push(Action::CompileEngine);
+        current_actions.push(Action::CompileEngine { options });
         AuroraEngineRepoActions {
             output_type: Default::default(),
             actions: current_actions,
@@ -63,8 +200,15 @@ impl<T> AuroraEngineRepoActions<T> {
     }
 
     pub fn compile_xcc_router_contract(self) -> AuroraEngineRepoActions<Vec<u8>> {
+        self.compile_xcc_router_contract_with(CompileOptions::xcc_router())
+    }
+
+    pub fn compile_xcc_router_contract_with(
+        self,
+        options: CompileOptions,
+    ) -> AuroraEngineRepoActions<Vec<u8>> {
         let mut current_actions = self.actions;
-        current_actions.push(Action::CompileXccRouter);
+        current_actions.push(Action::CompileXccRouter { options });
         AuroraEngineRepoActions {
             output_type: Default::default(),
             actions: current_actions,
@@ -74,8 +218,9 @@ impl<T> AuroraEngineRepoActions<T> {
 
 impl<T: TryFrom<ActionOutput, Error = anyhow::Error>> AuroraEngineRepoActions<T> {
     pub async fn execute(self) -> anyhow::Result<T> {
-        let _guard = ENGINE_LOCK.lock().await;
-        let engine_path = find_target_dir()?.join(ENGINE_PATH);
+        let target_dir = find_target_dir()?;
+        let _guard = FileLock::acquire(target_dir.join(ENGINE_LOCK_FILE)).await?;
+        let engine_path = target_dir.join(ENGINE_PATH);
         let mut output = ActionOutput::Unit;
         for action in self.actions {
             output = action.execute(&engine_path).await?;
@@ -87,87 +232,79 @@ impl<T: TryFrom<ActionOutput, Error = anyhow::Error>> AuroraEngineRepoActions<T>
 enum Action {
     Download,
     Checkout { version: String },
-    CompileEngine,
-    CompileXccRouter,
+    CompileEngine { options: CompileOptions },
+    CompileXccRouter { options: CompileOptions },
 }
 
 impl Action {
     async fn execute(self, engine_path: &Path) -> anyhow::Result<ActionOutput> {
         match self {
             Self::Download => {
-                if !engine_path.exists() {
-                    let target_dir = engine_path.parent().unwrap().canonicalize()?;
-                    let git = Git::in_working_dir(target_dir);
-                    git.clone("https://github.com/aurora-is-near/aurora-engine.git")
-                        .await?;
+                let git = Git::in_working_dir(engine_path);
+                if engine_path.exists() && !git.is_repo_root().await {
+                    // Left behind by an interrupted run.
+                    tokio::fs::remove_dir_all(engine_path).await?;
+                }
+                if engine_path.exists() {
+                    if git.is_dirty().await? {
+                        git.clean().await?;
+                    }
+                } else {
+                    // Commits are fetched on demand by the `Checkout` action, which avoids
+                    // downloading the whole history.
+                    tokio::fs::create_dir_all(engine_path).await?;
+                    git.init().await?;
+                    git.remote_add("origin", ENGINE_REPO_URL).await?;
                 }
                 Ok(ActionOutput::Unit)
             }
             Self::Checkout { version } => {
                 let git = Git::in_working_dir(engine_path);
-                git.fetch("origin").await?;
-                git.checkout(&version).await?;
+                // A branch or tag may have moved, so only commit hashes can be reused.
+                let is_commit_hash = git::is_commit_hash(&version);
+                let is_available = is_commit_hash && git.has_commit(&version).await;
+                let mut rev = version.clone();
+                if !is_available {
+                    let mirror = std::env::var(GIT_MIRROR_ENV_VAR).ok();
+                    let from_mirror = match mirror {
+                        Some(mirror) => fetch_from_mirror(&git, &mirror, &version).await,
+                        None => false,
+                    };
+                    if !from_mirror {
+                        git.fetch_shallow("origin", &version).await?;
+                    }
+                    if !is_commit_hash {
+                        // Fetching a branch or tag by name only updates `FETCH_HEAD`.
+                        rev = "FETCH_HEAD".into();
+                    }
+                }
+                git.checkout_verified(&rev).await?;
                 Ok(ActionOutput::Unit)
             }
-            Self::CompileEngine => {
+            Self::CompileEngine { options } => {
                 // For some reason `cargo` does not automatically pick up the toolchain file
                 // in the aurora-engine directory, so we manually read it and set the `RUSTUP_TOOLCHAIN`
                 // environment variable instead.
                 let toolchain = read_toolchain(engine_path).await?;
                 add_wasm_target(engine_path, &toolchain).await?;
-                let output = tokio::process::Command::new("cargo")
-                    .env("RUSTUP_TOOLCHAIN", &toolchain)
-                    .current_dir(engine_path)
-                    .args([
-                        "build",
-                        "--target",
-                        "wasm32-unknown-unknown",
-                        "--release",
-                        "--no-default-features",
-                        "--features=mainnet,integration-test",
-                        "-p",
-                        "aurora-engine",
-                        "-Z",
-                        "avoid-dev-deps",
-                    ])
-                    .output()
-                    .await?;
-                process::require_success(&output)?;
-                let binary_path = engine_path.join(
-                    [
-                        "target",
-                        "wasm32-unknown-unknown",
-                        "release",
-                        "aurora_engine.wasm",
-                    ]
-                    .iter()
-                    .collect::<PathBuf>(),
-                );
+                process::run(options.cargo_command(&toolchain, engine_path).args([
+                    "--no-default-features",
+                    "-p",
+                    "aurora-engine",
+                    "-Z",
+                    "avoid-dev-deps",
+                ]))
+                .await?;
+                let binary_path = options.binary_path(engine_path, "aurora_engine.wasm");
                 let bytes = tokio::fs::read(binary_path).await?;
                 Ok(ActionOutput::Bytes(bytes))
             }
-            Self::CompileXccRouter => {
+            Self::CompileXccRouter { options } => {
                 let toolchain = read_toolchain(engine_path).await?;
                 add_wasm_target(engine_path, &toolchain).await?;
                 let router_path = engine_path.join("etc").join("xcc-router");
-                let output = tokio::process::Command::new("cargo")
-                    .env("RUSTUP_TOOLCHAIN", &toolchain)
-                    .env("RUSTFLAGS", "-C link-arg=-s")
-                    .current_dir(&router_path)
-                    .args(["build", "--target", "wasm32-unknown-unknown", "--release"])
-                    .output()
-                    .await?;
-                process::require_success(&output)?;
-                let binary_path = router_path.join(
-                    [
-                        "target",
-                        "wasm32-unknown-unknown",
-                        "release",
-                        "xcc_router.wasm",
-                    ]
-                    .iter()
-                    .collect::<PathBuf>(),
-                );
+                process::run(&mut options.cargo_command(&toolchain, &router_path)).await?;
+                let binary_path = options.binary_path(&router_path, "xcc_router.wasm");
                 let bytes = tokio::fs::read(binary_path).await?;
                 Ok(ActionOutput::Bytes(bytes))
             }
@@ -175,6 +312,51 @@ impl Action {
     }
 }
 
+/// Returns the cached contract compiled from `LATEST_ENGINE_VERSION` with the given options,
+/// or compiles it with `compile` and adds it to the cache.
+async fn cached_wasm<F, Fut>(
+    name: &str,
+    options: &CompileOptions,
+    compile: F,
+) -> anyhow::Result<Vec<u8>>
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<Vec<u8>>>,
+{
+    let cache_dir = find_target_dir()?.join(WASM_CACHE_DIR);
+    let cache_path = cache_dir.join(format!(
+        "{name}-{LATEST_ENGINE_VERSION}-{}.wasm",
+        options.artifact_id()
+    ));
+    if let Ok(bytes) = tokio::fs::read(&cache_path).await {
+        return Ok(bytes);
+    }
+
+    let bytes = compile().await?;
+    // Write to a temporary file first, so that other processes never read a partial file.
+    tokio::fs::create_dir_all(&cache_dir).await?;
+    let tmp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+    tokio::fs::write(&tmp_path, &bytes).await?;
+    tokio::fs::rename(&tmp_path, &cache_path).await?;
+    Ok(bytes)
+}
+
+/// Fetches the commit, branch or tag `version` from the mirror. Returns `false` if the
+/// mirror does not have it.
+async fn fetch_from_mirror(git: &Git, mirror: &str, version: &str) -> bool {
+    if git.fetch_refspecs(mirror, &[version]).await.is_ok() {
+        return true;
+    }
+    // Bundles (and servers which do not allow fetching unadvertised commits) only serve
+    // their refs, so all of them are fetched in the hope that one contains the commit.
+    git::is_commit_hash(version)
+        && git
+            .fetch_refspecs(mirror, &["+refs/*:refs/remotes/mirror/*"])
+            .await
+            .is_ok()
+        && git.has_commit(version).await
+}
+
 async fn read_toolchain(engine_path: &Path) -> anyhow::Result<String> {
     let bytes = tokio::fs::read(engine_path.join("rust-toolchain")).await?;
     let value: toml::Value = toml::from_slice(&bytes)?;
@@ -190,13 +372,13 @@ async fn read_toolchain(engine_path: &Path) -> anyhow::Result<String> {
 }
 
 async fn add_wasm_target(engine_path: &Path, toolchain: &str) -> anyhow::Result<()> {
-    let output = tokio::process::Command::new("rustup")
-        .env("RUSTUP_TOOLCHAIN", toolchain)
-        .current_dir(engine_path)
-        .args(["target", "add", "wasm32-unknown-unknown"])
-        .output()
-        .await?;
-    process::require_success(&output)?;
+    process::run(
+        tokio::process::Command::new("rustup")
+            .env("RUSTUP_TOOLCHAIN", toolchain)
+            .current_dir(engine_path)
+            .args(["target", "add", WASM_TARGET]),
+    )
+    .await?;
     Ok(())
 }
 
@@ -245,6 +427,31 @@ impl TryFrom<ActionOutput> for () {
     }
 }
 
+#[test]
+fn test_compile_options_artifact_id() {
+    let mainnet = CompileOptions::mainnet();
+    let reordered = CompileOptions {
+        features: vec!["integration-test".into(), "mainnet".into()],
+        ..CompileOptions::mainnet()
+    };
+    assert_eq!(mainnet.artifact_id(), reordered.artifact_id());
+    assert_ne!(
+        mainnet.artifact_id(),
+        CompileOptions::testnet().artifact_id()
+    );
+    let dev = CompileOptions {
+        profile: "dev".into(),
+        ..CompileOptions::mainnet()
+    };
+    assert_ne!(mainnet.artifact_id(), dev.artifact_id());
+    assert_eq!(
+        dev.binary_path(Path::new("engine"), "aurora_engine.wasm"),
+        Path::new("engine/target")
+            .join(dev.artifact_id())
+            .join("wasm32-unknown-unknown/debug/aurora_engine.wasm")
+    );
+}
+
 #[test]
 fn test_find_target_dir() {
     let result = find_target_dir().unwrap();
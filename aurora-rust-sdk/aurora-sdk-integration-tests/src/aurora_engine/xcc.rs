@@ -0,0 +1,116 @@
+//! Helpers for the engine's cross-contract calls (XCC). Each EVM address which uses the XCC
+//! precompile gets a `{address}.{engine}` sub-account running the xcc-router contract, which
+//! executes the promises on NEAR. Promises scheduled with `lazy_transact` are stored in the
+//! router's state until `execute_scheduled` is called with their nonce.
+
+use crate::{
+    aurora_engine::{AuroraEngine, MintWnearOutcome},
+    wnear::Wnear,
+};
+use aurora_engine_types::{borsh::BorshDeserialize, parameters::PromiseArgs, types::Address};
+use workspaces::{network::Sandbox, AccountId, Worker};
+
+/// Storage prefix of the router's `scheduled_promises` map (the borsh-serialized
+/// `StorageKey::Map` variant). The keys are followed by the borsh-serialized `u64` nonce.
+const SCHEDULED_PROMISES_PREFIX: u8 = 3;
+
+impl AuroraEngine {
+    /// The sub-account which executes the cross-contract calls of `address`.
+    /// Fails if the engine account ID is too long for the sub-account ID to be valid.
+    pub fn xcc_sub_account_id(&self, address: Address) -> anyhow::Result<AccountId> {
+        let sub_account_id = format!("{}.{}", address.encode(), self.inner.id()).parse()?;
+        Ok(sub_account_id)
+    }
+
+    /// The EVM address of the XCC sub-account of `address`. This address must hold wNEAR
+    /// to pay for the sub-account's creation and the NEAR attached to the promises.
+    pub fn xcc_implicit_address(&self, address: Address) -> anyhow::Result<Address> {
+        let sub_account_id = self.xcc_sub_account_id(address)?;
+        Ok(aurora_engine_sdk::types::near_account_to_evm_address(
+            sub_account_id.as_bytes(),
+        ))
+    }
+
+    /// Whether the XCC sub-account of `address` was created, i.e. whether `address` has
+    /// used the XCC precompile.
+    pub async fn xcc_sub_account_exists(
+        &self,
+        worker: &Worker<Sandbox>,
+        address: Address,
+    ) -> anyhow::Result<bool> {
+        let sub_account_id = self.xcc_sub_account_id(address)?;
+        Ok(worker.view_account(&sub_account_id).await.is_ok())
+    }
+
+    /// The version of the router deployed to the XCC sub-account of `address`.
+    pub async fn xcc_router_version(
+        &self,
+        worker: &Worker<Sandbox>,
+        address: Address,
+    ) -> anyhow::Result<u32> {
+        let sub_account_id = self.xcc_sub_account_id(address)?;
+        let outcome = worker.view(&sub_account_id, "get_version").await?;
+        Ok(outcome.json()?)
+    }
+
+    /// The promises scheduled (with `lazy_transact`) by `address` which were not executed
+    /// yet, ordered by nonce.
+    pub async fn xcc_scheduled_promises(
+        &self,
+        worker: &Worker<Sandbox>,
+        address: Address,
+    ) -> anyhow::Result<Vec<(u64, PromiseArgs)>> {
+        let sub_account_id = self.xcc_sub_account_id(address)?;
+        let state = worker
+            .view_state(&sub_account_id)
+            .prefix(&[SCHEDULED_PROMISES_PREFIX])
+            .await?;
+        let mut promises = state
+            .into_iter()
+            .map(|(key, value)| {
+                let nonce = u64::try_from_slice(&key[1..])?;
+                let promise = PromiseArgs::try_from_slice(&value)?;
+                Ok((nonce, promise))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        promises.sort_by_key(|(nonce, _)| *nonce);
+        Ok(promises)
+    }
+
+    /// Executes the promise which `address` scheduled with the given nonce. Anyone can
+    /// call `execute_scheduled`; this signs with the engine account.
+    pub async fn xcc_execute_scheduled(&self, address: Address, nonce: u64) -> anyhow::Result<()> {
+        self.xcc_execute_scheduled_with(self.inner.as_account(), address, nonce)
+            .await
+    }
+
+    pub async fn xcc_execute_scheduled_with(
+        &self,
+        account: &workspaces::Account,
+        address: Address,
+        nonce: u64,
+    ) -> anyhow::Result<()> {
+        let sub_account_id = self.xcc_sub_account_id(address)?;
+        // The router takes the nonce as a JSON `U64`, i.e. a string.
+        account
+            .call(&sub_account_id, "execute_scheduled")
+            .args_json(serde_json::json!({ "nonce": nonce.to_string() }))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(())
+    }
+
+    /// Mints `amount` wNEAR to the implicit address of the XCC sub-account of `address`.
+    /// Returns the outcome of `mint_wnear`.
+    pub async fn fund_xcc_sub_account(
+        &self,
+        wnear: &Wnear,
+        address: Address,
+        amount: u128,
+    ) -> anyhow::Result<MintWnearOutcome> {
+        let implicit_address = self.xcc_implicit_address(address)?;
+        self.mint_wnear(wnear, implicit_address, amount).await
+    }
+}
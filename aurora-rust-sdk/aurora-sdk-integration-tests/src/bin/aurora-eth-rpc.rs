@@ -0,0 +1,62 @@
+//! Starts a sandbox with a freshly deployed Aurora Engine and serves an Ethereum JSON-RPC
+//! endpoint for it.
+//!
+//! Usage: `aurora-eth-rpc [-h | --help] [LISTEN_ADDR] [FUNDED_ADDRESS...]`
+//!
+//! `LISTEN_ADDR` defaults to `127.0.0.1:8545`. Each `FUNDED_ADDRESS` (hex, with or without
+//! the `0x` prefix) is given an initial balance of 10 ETH so that it can pay for transactions.
+//!
+//! Receipts and logs are kept in memory by the endpoint: `eth_getTransactionReceipt` and
+//! `eth_getLogs` only return transactions submitted through its `eth_sendRawTransaction`.
+
+use aurora_sdk_integration_tests::{
+    aurora_engine,
+    aurora_engine_types::types::{Address, Wei},
+    eth_rpc::{self, EthRpc},
+    tokio, workspaces,
+};
+
+const DEFAULT_LISTEN_ADDR: &str = "127.0.0.1:8545";
+const INITIAL_BALANCE: u64 = 10_000_000_000_000_000_000;
+const USAGE: &str = "\
+Usage: aurora-eth-rpc [LISTEN_ADDR] [FUNDED_ADDRESS...]
+
+Starts a sandbox with a freshly deployed Aurora Engine and serves an Ethereum JSON-RPC
+endpoint for it.
+
+  LISTEN_ADDR      Address to listen on (default: 127.0.0.1:8545)
+  FUNDED_ADDRESS   Hex address given an initial balance of 10 ETH
+
+eth_getTransactionReceipt and eth_getLogs only return transactions submitted through
+this endpoint's eth_sendRawTransaction; they are kept in memory, not read from the chain.";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    if std::env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return Ok(());
+    }
+
+    let mut args = std::env::args().skip(1);
+    let listen_addr = args
+        .next()
+        .unwrap_or_else(|| DEFAULT_LISTEN_ADDR.into())
+        .parse()?;
+    let funded_addresses = args
+        .map(|arg| Address::decode(arg.strip_prefix("0x").unwrap_or(&arg)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::Error::msg(format!("Invalid address: {e:?}")))?;
+
+    let worker = workspaces::sandbox().await?;
+    let engine = aurora_engine::deploy_latest(&worker).await?;
+    for address in funded_addresses {
+        engine
+            .mint_account(address, 0, Wei::new_u64(INITIAL_BALANCE))
+            .await?;
+    }
+
+    let (local_addr, handle) = eth_rpc::spawn(EthRpc::new(worker, engine), listen_addr)?;
+    println!("Aurora Ethereum JSON-RPC listening on http://{local_addr}");
+    handle.await??;
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! Helpers for the engine's ETH connector, which bridges the base token (ETH) between
+//! Ethereum, NEAR (as the NEP-141 token nETH implemented by the engine itself) and the EVM.
+//! Deposits from Ethereum are simulated with a stand-in prover which accepts any proof.
+
+use crate::{aurora_engine::AuroraEngine, nep141::AccountIdArgs};
+use ::aurora_engine::{
+    parameters::{SetContractDataCallArgs, WithdrawCallArgs, WithdrawResult},
+    proof::Proof,
+};
+use aurora_engine_types::types::{Address, NEP141Wei};
+use std::sync::atomic::{AtomicU64, Ordering};
+use workspaces::{network::Sandbox, types::NearToken, Account, AccountId, Contract, Worker};
+
+/// A prover contract whose `verify_log_entry` always returns (borsh-serialized) `true`.
+/// NEAR contracts must import their memory instead of declaring it.
+const MOCK_PROVER_WAT: &str = r#"
+(module
+  (import "env" "memory" (memory 1))
+  (import "env" "value_return" (func $value_return (param i64 i64)))
+  (data (i32.const 0) "\01")
+  (func (export "verify_log_entry")
+    (call $value_return (i64.const 1) (i64.const 0))))
+"#;
+const DEPOSITED_EVENT_SIGNATURE: &str = "Deposited(address,string,uint256,uint256)";
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+
+/// Every proof gets different header data, since the connector rejects proofs it has
+/// already seen.
+static PROOF_NONCE: AtomicU64 = AtomicU64::new(0);
+
+pub struct EthConnector {
+    pub prover: Contract,
+    /// Address of the (simulated) custodian contract on Ethereum which emits the
+    /// `Deposited` events.
+    pub custodian_address: Address,
+}
+
+impl EthConnector {
+    /// Deploys the stand-in prover and configures the engine's connector to use it
+    /// together with the given custodian address.
+    pub async fn deploy(
+        worker: &Worker<Sandbox>,
+        engine: &AuroraEngine,
+        custodian_address: Address,
+    ) -> anyhow::Result<Self> {
+        let wasm = wat::parse_str(MOCK_PROVER_WAT)?;
+        let prover = worker.dev_deploy(&wasm).await?;
+        let args = SetContractDataCallArgs {
+            prover_account: prover.id().as_str().parse().unwrap(),
+            eth_custodian_address: custodian_address.encode(),
+            metadata: Default::default(),
+        };
+        engine
+            .inner
+            .call("set_eth_connector_contract_data")
+            .args_borsh(args)
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(Self {
+            prover,
+            custodian_address,
+        })
+    }
+
+    /// Creates a proof of a `Deposited` event of the custodian which sends `amount` wei
+    /// (without relayer fee) to the NEAR account `recipient` as nETH.
+    pub fn create_deposit_proof(&self, recipient: &AccountId, amount: u128) -> Proof {
+        let sender = Address::zero();
+        let topics = vec![
+            aurora_engine_sdk::keccak(DEPOSITED_EVENT_SIGNATURE.as_bytes())
+                .as_bytes()
+                .to_vec(),
+            ethabi::encode(&[ethabi::Token::Address(sender.raw())]),
+        ];
+        let data = ethabi::encode(&[
+            ethabi::Token::String(recipient.to_string()),
+            ethabi::Token::Uint(amount.into()),
+            ethabi::Token::Uint(0.into()),
+        ]);
+        // A log entry is RLP-encoded as `[address, [topics...], data]`.
+        let mut stream = rlp::RlpStream::new_list(3);
+        stream
+            .append(&self.custodian_address.as_bytes())
+            .append_list::<Vec<u8>, _>(&topics)
+            .append(&data);
+        let log_entry_data = stream.out().to_vec();
+        let nonce = PROOF_NONCE.fetch_add(1, Ordering::Relaxed);
+
+        Proof {
+            log_index: 0,
+            log_entry_data,
+            receipt_index: 0,
+            receipt_data: Vec::new(),
+            header_data: nonce.to_be_bytes().to_vec(),
+            proof: Vec::new(),
+        }
+    }
+
+    /// Deposits `amount` wei from Ethereum to `recipient` as nETH on NEAR. The deposit is
+    /// submitted by `relayer`.
+    pub async fn deposit(
+        &self,
+        engine: &AuroraEngine,
+        relayer: &Account,
+        recipient: &AccountId,
+        amount: u128,
+    ) -> anyhow::Result<()> {
+        let balance_before = engine.ft_balance_of(recipient).await?;
+        relayer
+            .call(engine.inner.id(), "deposit")
+            .args_borsh(self.create_deposit_proof(recipient, amount))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        let balance_after = engine.ft_balance_of(recipient).await?;
+        if balance_after.checked_sub(balance_before) != Some(amount) {
+            return Err(anyhow::Error::msg(format!(
+                "Deposit of {amount} wei to {recipient} failed: the nETH balance changed from \
+                 {balance_before} to {balance_after}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Deposits `amount` wei from Ethereum to `account` (as nETH) and moves it on to
+    /// `recipient` in the EVM.
+    pub async fn deposit_to_evm(
+        &self,
+        engine: &AuroraEngine,
+        account: &Account,
+        recipient: Address,
+        amount: u128,
+    ) -> anyhow::Result<()> {
+        self.deposit(engine, account, account.id(), amount).await?;
+        engine
+            .eth_ft_transfer_call(account, recipient, amount)
+            .await
+    }
+}
+
+/// Helpers for the NEP-141 (nETH) interface and the withdrawals of the ETH connector.
+impl AuroraEngine {
+    /// The nETH balance of a NEAR account.
+    pub async fn ft_balance_of(&self, account_id: &AccountId) -> anyhow::Result<u128> {
+        let outcome = self
+            .inner
+            .view("ft_balance_of")
+            .args_json(AccountIdArgs { account_id })
+            .await?;
+        let result: String = outcome.json()?;
+        Ok(result.parse()?)
+    }
+
+    /// The amount of ETH held by EVM addresses.
+    pub async fn ft_total_eth_supply_on_aurora(&self) -> anyhow::Result<u128> {
+        let outcome = self.inner.view("ft_total_eth_supply_on_aurora").await?;
+        let result: String = outcome.json()?;
+        Ok(result.parse()?)
+    }
+
+    /// The amount of ETH held by NEAR accounts as nETH.
+    pub async fn ft_total_eth_supply_on_near(&self) -> anyhow::Result<u128> {
+        let outcome = self.inner.view("ft_total_eth_supply_on_near").await?;
+        let result: String = outcome.json()?;
+        Ok(result.parse()?)
+    }
+
+    /// Moves `amount` nETH from the NEAR account `sender` to `recipient` in the EVM, using
+    /// `ft_transfer_call` with the engine as the receiver. Checks the EVM balance of the
+    /// recipient afterwards.
+    pub async fn eth_ft_transfer_call(
+        &self,
+        sender: &Account,
+        recipient: Address,
+        amount: u128,
+    ) -> anyhow::Result<()> {
+        let balance_before = self.get_balance(recipient).await?;
+        // The message is `{relayer}:{fee}{recipient}`, with the fee as 32 (hex-encoded) bytes.
+        let msg = format!(
+            "{}:{}{}",
+            sender.id(),
+            hex::encode([0u8; 32]),
+            recipient.encode()
+        );
+        sender
+            .call(self.inner.id(), "ft_transfer_call")
+            .args_json(serde_json::json!({
+                "receiver_id": self.inner.id(),
+                "amount": amount.to_string(),
+                "msg": msg,
+            }))
+            .deposit(ONE_YOCTO)
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        let balance_after = self.get_balance(recipient).await?;
+        if balance_after.raw().checked_sub(balance_before.raw()) != Some(amount.into()) {
+            return Err(anyhow::Error::msg(format!(
+                "Transfer of {amount} nETH to {} failed: the balance changed from {} to {}",
+                recipient.encode(),
+                balance_before.raw(),
+                balance_after.raw(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Burns `amount` nETH of `account` to withdraw it to `recipient` on Ethereum. The
+    /// returned result is the data the custodian uses to release the ETH.
+    pub async fn withdraw_eth(
+        &self,
+        account: &Account,
+        recipient: Address,
+        amount: u128,
+    ) -> anyhow::Result<WithdrawResult> {
+        let args = WithdrawCallArgs {
+            recipient_address: recipient,
+            amount: NEP141Wei::new(amount),
+        };
+        let outcome = account
+            .call(self.inner.id(), "withdraw")
+            .args_borsh(args)
+            .deposit(ONE_YOCTO)
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(outcome.borsh()?)
+    }
+}
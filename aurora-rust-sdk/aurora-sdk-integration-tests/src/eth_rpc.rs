@@ -0,0 +1,579 @@
+//! A local Ethereum JSON-RPC endpoint backed by an Aurora Engine deployed in a sandbox.
+//! This allows tools that only speak `eth_*` JSON-RPC (frontends, Foundry scripts, etc)
+//! to interact with the same engine that the Rust integration tests use.
+//!
+//! Only a subset of the Ethereum JSON-RPC API is supported; each method is translated
+//! into the equivalent Aurora Engine method. Transactions are executed synchronously,
+//! so a receipt is available as soon as `eth_sendRawTransaction` returns. Block numbers
+//! and hashes are those of the NEAR block the engine executed the transaction in.
+//!
+//! Receipts and logs are not read back from the chain: `eth_getTransactionReceipt` and
+//! `eth_getLogs` only see the transactions submitted through this endpoint's
+//! `eth_sendRawTransaction`, which are kept in memory. Transactions sent to the engine
+//! directly (e.g. with `AuroraEngine::call_evm_contract`) are never returned.
+
+use crate::aurora_engine::{error, AuroraEngine, ContractInput};
+use aurora_engine_sdk::keccak;
+use aurora_engine_transactions::{EthTransactionKind, NormalizedEthTransaction};
+use aurora_engine_types::{
+    parameters::engine::{ResultLog, SubmitResult, TransactionStatus},
+    types::{Address, Wei},
+    H256, U256,
+};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use serde_json::{json, Value};
+use std::{collections::HashMap, convert::Infallible, net::SocketAddr, sync::Arc, sync::Mutex};
+use workspaces::{network::Sandbox, result::ExecutionFinalResult, Worker};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+/// Error code used by Geth (and expected by most tooling) for EVM reverts.
+const EXECUTION_REVERTED: i64 = 3;
+
+/// Translates Ethereum JSON-RPC requests into calls to the given Aurora Engine.
+pub struct EthRpc {
+    worker: Worker<Sandbox>,
+    engine: AuroraEngine,
+    receipts: Mutex<ReceiptStore>,
+}
+
+#[derive(Default)]
+struct ReceiptStore {
+    by_hash: HashMap<H256, TransactionReceipt>,
+    /// Transaction hashes in the order they were submitted.
+    order: Vec<H256>,
+}
+
+#[derive(Debug, Clone)]
+struct TransactionReceipt {
+    transaction_hash: H256,
+    block_hash: H256,
+    block_number: u64,
+    from: Address,
+    to: Option<Address>,
+    contract_address: Option<Address>,
+    result: SubmitResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(INVALID_PARAMS, message)
+    }
+
+    fn server(err: impl std::fmt::Display) -> Self {
+        Self::new(SERVER_ERROR, err.to_string())
+    }
+
+    fn from_status(status: TransactionStatus) -> Self {
+        match status {
+            TransactionStatus::Revert(bytes) => Self {
+                code: EXECUTION_REVERTED,
+                message: format!(
+                    "execution reverted: {}",
+                    error::parse_evm_revert_message(&bytes)
+                ),
+                data: Some(Value::String(encode_data(&bytes))),
+            },
+            other => Self::new(SERVER_ERROR, format!("{other:?}")),
+        }
+    }
+}
+
+impl EthRpc {
+    pub fn new(worker: Worker<Sandbox>, engine: AuroraEngine) -> Self {
+        Self {
+            worker,
+            engine,
+            receipts: Mutex::new(ReceiptStore::default()),
+        }
+    }
+
+    pub fn engine(&self) -> &AuroraEngine {
+        &self.engine
+    }
+
+    /// Handles a single JSON-RPC request object or a batch (array) of them, returning the
+    /// corresponding JSON-RPC response.
+    pub async fn handle(&self, request: Value) -> Value {
+        match request {
+            Value::Array(requests) => {
+                let mut responses = Vec::with_capacity(requests.len());
+                for request in requests {
+                    responses.push(self.handle_single(request).await);
+                }
+                Value::Array(responses)
+            }
+            request => self.handle_single(request).await,
+        }
+    }
+
+    async fn handle_single(&self, request: Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method,
+            None => return error_response(id, RpcError::new(INVALID_REQUEST, "Missing method")),
+        };
+        let params: &[Value] = match request.get("params") {
+            Some(Value::Array(params)) => params.as_slice(),
+            None | Some(Value::Null) => &[],
+            Some(_) => {
+                return error_response(id, RpcError::invalid_params("Params must be an array"))
+            }
+        };
+        match self.dispatch(method, params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(err) => error_response(id, err),
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: &[Value]) -> Result<Value, RpcError> {
+        match method {
+            "eth_chainId" => self.chain_id().await,
+            "eth_getBalance" => self.get_balance(params).await,
+            "eth_call" => self.call(params).await,
+            "eth_sendRawTransaction" => self.send_raw_transaction(params).await,
+            "eth_getTransactionReceipt" => self.get_transaction_receipt(params),
+            "eth_getCode" => self.get_code(params).await,
+            "eth_getStorageAt" => self.get_storage_at(params).await,
+            "eth_getLogs" => self.get_logs(params),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not supported: {method}"),
+            )),
+        }
+    }
+
+    async fn chain_id(&self) -> Result<Value, RpcError> {
+        let chain_id = self.engine.get_chain_id().await.map_err(RpcError::server)?;
+        Ok(encode_quantity(chain_id))
+    }
+
+    async fn get_balance(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let address = parse_address(param(params, 0)?)?;
+        let balance = self
+            .engine
+            .get_balance(address)
+            .await
+            .map_err(RpcError::server)?;
+        Ok(encode_quantity(balance.raw()))
+    }
+
+    async fn call(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let tx = param(params, 0)?;
+        let to = tx
+            .get("to")
+            .filter(|v| !v.is_null())
+            .ok_or_else(|| RpcError::invalid_params("eth_call requires a `to` address"))
+            .and_then(parse_address)?;
+        let sender = tx
+            .get("from")
+            .filter(|v| !v.is_null())
+            .map(parse_address)
+            .transpose()?;
+        let value = tx
+            .get("value")
+            .filter(|v| !v.is_null())
+            .map(parse_quantity)
+            .transpose()?
+            .map(Wei::new)
+            .unwrap_or_else(Wei::zero);
+        let input = tx
+            .get("input")
+            .or_else(|| tx.get("data"))
+            .filter(|v| !v.is_null())
+            .map(parse_data)
+            .transpose()?
+            .unwrap_or_default();
+
+        let status = self
+            .engine
+            .view_evm_contract(to, ContractInput(input), sender, value)
+            .await
+            .map_err(RpcError::server)?;
+        match status {
+            TransactionStatus::Succeed(bytes) => Ok(Value::String(encode_data(&bytes))),
+            status => Err(RpcError::from_status(status)),
+        }
+    }
+
+    async fn send_raw_transaction(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let raw_tx = parse_data(param(params, 0)?)?;
+        let tx = EthTransactionKind::try_from(raw_tx.as_slice())
+            .ok()
+            .and_then(|kind| NormalizedEthTransaction::try_from(kind).ok())
+            .ok_or_else(|| RpcError::invalid_params("Failed to parse raw transaction"))?;
+        let transaction_hash = keccak(&raw_tx);
+
+        let outcome = self
+            .engine
+            .inner
+            .call("submit")
+            .args(raw_tx)
+            .max_gas()
+            .transact()
+            .await
+            .map_err(RpcError::server)?;
+        let (block_hash, block_number) = self.engine_block(&outcome).await?;
+        let outcome = outcome
+            .into_result()
+            .map_err(|e| RpcError::server(format!("{e:?}")))?;
+        let result: SubmitResult = outcome.borsh().map_err(RpcError::server)?;
+
+        let contract_address = match (&tx.to, &result.status) {
+            (None, TransactionStatus::Succeed(bytes)) => Address::try_from_slice(bytes).ok(),
+            _ => None,
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash,
+            block_hash,
+            block_number,
+            from: tx.address,
+            to: tx.to,
+            contract_address,
+            result,
+        };
+        let mut receipts = self.receipts.lock().unwrap();
+        receipts.by_hash.insert(transaction_hash, receipt);
+        receipts.order.push(transaction_hash);
+
+        Ok(Value::String(encode_data(transaction_hash.as_bytes())))
+    }
+
+    fn get_transaction_receipt(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let hash = parse_hash(param(params, 0)?)?;
+        let receipts = self.receipts.lock().unwrap();
+        Ok(receipts
+            .by_hash
+            .get(&hash)
+            .map(TransactionReceipt::to_json)
+            .unwrap_or(Value::Null))
+    }
+
+    async fn get_code(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let address = parse_address(param(params, 0)?)?;
+        let code = self
+            .engine
+            .get_code(address)
+            .await
+            .map_err(RpcError::server)?;
+        Ok(Value::String(encode_data(&code)))
+    }
+
+    async fn get_storage_at(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let address = parse_address(param(params, 0)?)?;
+        let mut key = H256::zero();
+        parse_quantity(param(params, 1)?)?.to_big_endian(key.as_bytes_mut());
+        let value = self
+            .engine
+            .get_storage_at(address, key)
+            .await
+            .map_err(RpcError::server)?;
+        Ok(Value::String(encode_data(value.as_bytes())))
+    }
+
+    /// Only searches the receipts in the in-memory store, i.e. transactions submitted
+    /// with `eth_sendRawTransaction` on this endpoint.
+    fn get_logs(&self, params: &[Value]) -> Result<Value, RpcError> {
+        let filter = LogFilter::parse(param(params, 0)?)?;
+        let receipts = self.receipts.lock().unwrap();
+        let logs: Vec<Value> = receipts
+            .order
+            .iter()
+            .filter_map(|hash| receipts.by_hash.get(hash))
+            .filter(|receipt| filter.matches_block(receipt))
+            .flat_map(|receipt| {
+                receipt
+                    .result
+                    .logs
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, log)| filter.matches_log(log))
+                    .map(|(index, log)| receipt.log_to_json(index, log))
+            })
+            .collect();
+        Ok(Value::Array(logs))
+    }
+
+    /// Finds the NEAR block in which the engine executed the transaction.
+    async fn engine_block(&self, outcome: &ExecutionFinalResult) -> Result<(H256, u64), RpcError> {
+        let engine_id = self.engine.inner.id();
+        let execution = outcome
+            .outcomes()
+            .into_iter()
+            .rev()
+            .find(|o| &o.executor_id == engine_id)
+            .unwrap_or_else(|| outcome.outcome());
+        let block = self
+            .worker
+            .view_block()
+            .block_hash(execution.block_hash)
+            .await
+            .map_err(RpcError::server)?;
+        Ok((H256(block.hash().0), block.height()))
+    }
+}
+
+impl TransactionReceipt {
+    fn to_json(&self) -> Value {
+        let success = matches!(self.result.status, TransactionStatus::Succeed(_));
+        let logs: Vec<Value> = self
+            .result
+            .logs
+            .iter()
+            .enumerate()
+            .map(|(index, log)| self.log_to_json(index, log))
+            .collect();
+        json!({
+            "transactionHash": encode_data(self.transaction_hash.as_bytes()),
+            "transactionIndex": "0x0",
+            "blockHash": encode_data(self.block_hash.as_bytes()),
+            "blockNumber": encode_quantity(self.block_number),
+            "from": encode_address(self.from),
+            "to": self.to.map(encode_address),
+            "cumulativeGasUsed": encode_quantity(self.result.gas_used),
+            "gasUsed": encode_quantity(self.result.gas_used),
+            "effectiveGasPrice": "0x0",
+            "contractAddress": self.contract_address.map(encode_address),
+            "logs": logs,
+            "logsBloom": encode_data(&[0u8; 256]),
+            "type": "0x0",
+            "status": if success { "0x1" } else { "0x0" },
+        })
+    }
+
+    fn log_to_json(&self, index: usize, log: &ResultLog) -> Value {
+        let topics: Vec<Value> = log
+            .topics
+            .iter()
+            .map(|topic| Value::String(encode_data(topic)))
+            .collect();
+        json!({
+            "address": encode_address(log.address),
+            "topics": topics,
+            "data": encode_data(&log.data),
+            "blockHash": encode_data(self.block_hash.as_bytes()),
+            "blockNumber": encode_quantity(self.block_number),
+            "transactionHash": encode_data(self.transaction_hash.as_bytes()),
+            "transactionIndex": "0x0",
+            "logIndex": encode_quantity(index),
+            "removed": false,
+        })
+    }
+}
+
+/// The subset of the `eth_getLogs` filter object that this endpoint understands.
+struct LogFilter {
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    block_hash: Option<H256>,
+    addresses: Vec<Address>,
+    /// `None` at a position matches any topic; otherwise one of the given topics must match.
+    topics: Vec<Option<Vec<H256>>>,
+}
+
+impl LogFilter {
+    fn parse(value: &Value) -> Result<Self, RpcError> {
+        let block_number = |key: &str| -> Result<Option<u64>, RpcError> {
+            match value.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::String(tag))
+                    if matches!(tag.as_str(), "latest" | "pending" | "safe" | "finalized") =>
+                {
+                    Ok(None)
+                }
+                Some(Value::String(tag)) if tag == "earliest" => Ok(Some(0)),
+                Some(v) => Ok(Some(parse_quantity(v)?.low_u64())),
+            }
+        };
+        let addresses: Vec<Address> = match value.get("address") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(xs)) => xs.iter().map(parse_address).collect::<Result<_, _>>()?,
+            Some(x) => vec![parse_address(x)?],
+        };
+        let topics: Vec<Option<Vec<H256>>> = match value.get("topics") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(xs)) => xs
+                .iter()
+                .map(|x| match x {
+                    Value::Null => Ok(None),
+                    Value::Array(ys) => ys
+                        .iter()
+                        .map(parse_hash)
+                        .collect::<Result<_, _>>()
+                        .map(Some),
+                    y => parse_hash(y).map(|h| Some(vec![h])),
+                })
+                .collect::<Result<_, _>>()?,
+            Some(_) => return Err(RpcError::invalid_params("Topics must be an array")),
+        };
+        let block_hash = value
+            .get("blockHash")
+            .filter(|v| !v.is_null())
+            .map(parse_hash)
+            .transpose()?;
+
+        Ok(Self {
+            from_block: block_number("fromBlock")?,
+            to_block: block_number("toBlock")?,
+            block_hash,
+            addresses,
+            topics,
+        })
+    }
+
+    fn matches_block(&self, receipt: &TransactionReceipt) -> bool {
+        if let Some(hash) = self.block_hash {
+            return receipt.block_hash == hash;
+        }
+        self.from_block.map_or(true, |n| receipt.block_number >= n)
+            && self.to_block.map_or(true, |n| receipt.block_number <= n)
+    }
+
+    fn matches_log(&self, log: &ResultLog) -> bool {
+        if !self.addresses.is_empty() && !self.addresses.contains(&log.address) {
+            return false;
+        }
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(i, expected)| match expected {
+                None => true,
+                Some(options) => log
+                    .topics
+                    .get(i)
+                    .map_or(false, |topic| options.iter().any(|o| &o.0 == topic)),
+            })
+    }
+}
+
+/// Serves the given `EthRpc` over HTTP at `addr` until the process is stopped.
+pub async fn serve(rpc: EthRpc, addr: SocketAddr) -> anyhow::Result<()> {
+    let (_, handle) = spawn(rpc, addr)?;
+    handle.await??;
+    Ok(())
+}
+
+/// Starts serving the given `EthRpc` over HTTP in a background task. Returns the address
+/// the server is bound to (useful when `addr` uses port 0) and the handle of the task.
+pub fn spawn(
+    rpc: EthRpc,
+    addr: SocketAddr,
+) -> anyhow::Result<(SocketAddr, tokio::task::JoinHandle<hyper::Result<()>>)> {
+    let rpc = Arc::new(rpc);
+    let make_service = make_service_fn(move |_| {
+        let rpc = rpc.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let rpc = rpc.clone();
+                async move { Ok::<_, Infallible>(handle_http(&rpc, request).await) }
+            }))
+        }
+    });
+    let server = hyper::Server::try_bind(&addr)?.serve(make_service);
+    let local_addr = server.local_addr();
+    Ok((local_addr, tokio::spawn(server)))
+}
+
+async fn handle_http(rpc: &EthRpc, request: Request<Body>) -> Response<Body> {
+    let method = request.method().clone();
+    let response = match method {
+        // Allow browser-based frontends to talk to the endpoint.
+        Method::OPTIONS => return http_response(StatusCode::OK, Body::empty()),
+        Method::POST => match hyper::body::to_bytes(request.into_body()).await {
+            Ok(bytes) => match serde_json::from_slice::<Value>(&bytes) {
+                Ok(value) => rpc.handle(value).await,
+                Err(e) => error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+            },
+            Err(e) => error_response(Value::Null, RpcError::new(PARSE_ERROR, e.to_string())),
+        },
+        _ => return http_response(StatusCode::METHOD_NOT_ALLOWED, Body::empty()),
+    };
+    http_response(StatusCode::OK, Body::from(response.to_string()))
+}
+
+fn http_response(status: StatusCode, body: Body) -> Response<Body> {
+    // Unwrap is safe because all the headers are statically known to be valid.
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "POST, OPTIONS")
+        .header("Access-Control-Allow-Headers", "Content-Type")
+        .body(body)
+        .unwrap()
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    let mut error_obj = json!({ "code": error.code, "message": error.message });
+    if let Some(data) = error.data {
+        error_obj["data"] = data;
+    }
+    json!({ "jsonrpc": "2.0", "id": id, "error": error_obj })
+}
+
+fn param(params: &[Value], index: usize) -> Result<&Value, RpcError> {
+    params
+        .get(index)
+        .ok_or_else(|| RpcError::invalid_params(format!("Missing parameter at index {index}")))
+}
+
+fn parse_hex_str(value: &Value) -> Result<&str, RpcError> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| RpcError::invalid_params(format!("Expected hex string, got {value}")))?;
+    Ok(s.strip_prefix("0x").unwrap_or(s))
+}
+
+fn parse_data(value: &Value) -> Result<Vec<u8>, RpcError> {
+    hex::decode(parse_hex_str(value)?).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+fn parse_quantity(value: &Value) -> Result<U256, RpcError> {
+    let s = parse_hex_str(value)?;
+    U256::from_str_radix(s, 16).map_err(|e| RpcError::invalid_params(format!("{e:?}")))
+}
+
+fn parse_address(value: &Value) -> Result<Address, RpcError> {
+    Address::decode(parse_hex_str(value)?).map_err(|e| RpcError::invalid_params(format!("{e:?}")))
+}
+
+fn parse_hash(value: &Value) -> Result<H256, RpcError> {
+    let bytes = parse_data(value)?;
+    if bytes.len() != 32 {
+        return Err(RpcError::invalid_params("Expected 32-byte hash"));
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+fn encode_data(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+fn encode_address(address: Address) -> String {
+    format!("0x{}", address.encode())
+}
+
+fn encode_quantity<T: Into<U256>>(value: T) -> Value {
+    Value::String(format!("0x{:x}", value.into()))
+}
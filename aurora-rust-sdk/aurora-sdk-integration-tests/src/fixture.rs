@@ -0,0 +1,294 @@
+//! A ready-made test environment: a sandbox with an engine, wNEAR and funded users.
+
+use crate::{
+    aurora_engine::{self, erc20::ERC20, AuroraEngine, CallOutcome},
+    snapshot::{start_sandbox, SandboxNode, SandboxSnapshot},
+    wnear::Wnear,
+};
+use aurora_engine_sdk::types::near_account_to_evm_address;
+use aurora_engine_types::{
+    types::{Address, Wei},
+    U256,
+};
+use workspaces::{
+    network::Sandbox,
+    types::{NearToken, SecretKey},
+    Account, AccountId, Contract, Worker,
+};
+
+/// 10 NEAR
+pub const DEFAULT_NEAR_BALANCE: u128 = 10_000_000_000_000_000_000_000_000;
+/// 5 wNEAR
+pub const DEFAULT_WNEAR_BALANCE: u128 = 5_000_000_000_000_000_000_000_000;
+/// Account (`{name}.test.near`) which wraps the NEAR for the users' wNEAR balances.
+const FUNDER_NAME: &str = "fixture-funder";
+/// NEAR given to the funder on top of the wNEAR balances, for gas and storage deposits.
+const FUNDER_EXTRA_BALANCE: u128 = 10_000_000_000_000_000_000_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserConfig {
+    /// The user's NEAR account is `{name}.test.near`.
+    pub name: String,
+    /// Balance of the NEAR account.
+    pub near_balance: u128,
+    /// wNEAR balance of the implicit EVM address (inside the engine).
+    pub wnear_balance: u128,
+    /// ETH balance of the implicit EVM address. It must fit in a `u64` (about 18.4 ETH),
+    /// see `AuroraEngine::mint_account`.
+    pub eth_balance: Wei,
+}
+
+impl UserConfig {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            near_balance: DEFAULT_NEAR_BALANCE,
+            wnear_balance: DEFAULT_WNEAR_BALANCE,
+            eth_balance: Wei::zero(),
+        }
+    }
+}
+
+pub struct FixtureUser {
+    pub name: String,
+    pub account: Account,
+    /// The EVM address which the engine associates with the NEAR account, i.e. the sender
+    /// of EVM transactions submitted by `account` with `call`.
+    pub address: Address,
+}
+
+pub struct AuroraFixture {
+    pub worker: Worker<Sandbox>,
+    pub engine: AuroraEngine,
+    pub wnear: Wnear,
+    users: Vec<FixtureUser>,
+    /// The fixture starts its own node (see `start_sandbox`) so that it can be
+    /// captured with `snapshot`.
+    _node: SandboxNode,
+}
+
+/// The state of a fixture, which can be restored into a fresh sandbox with
+/// `AuroraFixture::from_snapshot`. This is much faster than building the fixture again,
+/// so a test suite can build one fixture, snapshot it (e.g. in a `tokio::sync::OnceCell`)
+/// and give every test its own copy.
+#[derive(Debug, Clone)]
+pub struct FixtureSnapshot {
+    pub sandbox: SandboxSnapshot,
+    engine: SnapshotAccount,
+    wnear: SnapshotAccount,
+    wnear_token: (ethabi::Contract, Address),
+    users: Vec<(String, SnapshotAccount, Address)>,
+}
+
+#[derive(Debug, Clone)]
+struct SnapshotAccount {
+    id: AccountId,
+    secret_key: SecretKey,
+}
+
+impl SnapshotAccount {
+    fn new(account: &Account) -> Self {
+        Self {
+            id: account.id().clone(),
+            secret_key: account.secret_key().clone(),
+        }
+    }
+
+    fn account(&self, worker: &Worker<Sandbox>) -> Account {
+        Account::from_secret_key(self.id.clone(), self.secret_key.clone(), worker)
+    }
+
+    fn contract(&self, worker: &Worker<Sandbox>) -> Contract {
+        Contract::from_secret_key(self.id.clone(), self.secret_key.clone(), worker)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuroraFixtureBuilder {
+    users: Vec<UserConfig>,
+}
+
+impl AuroraFixtureBuilder {
+    /// Adds a user with the default balances.
+    pub fn user(self, name: &str) -> Self {
+        self.user_with_config(UserConfig::new(name))
+    }
+
+    pub fn user_with_balances(self, name: &str, wnear_balance: u128, eth_balance: Wei) -> Self {
+        self.user_with_config(UserConfig {
+            wnear_balance,
+            eth_balance,
+            ..UserConfig::new(name)
+        })
+    }
+
+    pub fn user_with_config(mut self, config: UserConfig) -> Self {
+        self.users.push(config);
+        self
+    }
+
+    pub async fn build(self) -> anyhow::Result<AuroraFixture> {
+        let (worker, node) = start_sandbox().await?;
+        let engine = aurora_engine::deploy_latest(&worker).await?;
+        let wnear = Wnear::deploy(&worker, &engine).await?;
+        let root = worker.root_account()?;
+        // The wNEAR is wrapped by a dedicated account and bridged like any deposit, so the
+        // NEAR of the engine account is not used.
+        let total_wnear_balance: u128 = self.users.iter().map(|c| c.wnear_balance).sum();
+        let funder = root
+            .create_subaccount(FUNDER_NAME)
+            .initial_balance(NearToken::from_yoctonear(
+                total_wnear_balance + FUNDER_EXTRA_BALANCE,
+            ))
+            .transact()
+            .await?
+            .into_result()?;
+
+        let mut users = Vec::with_capacity(self.users.len());
+        for config in self.users {
+            let account = root
+                .create_subaccount(&config.name)
+                .initial_balance(NearToken::from_yoctonear(config.near_balance))
+                .transact()
+                .await?
+                .into_result()?;
+            let address = near_account_to_evm_address(account.id().as_bytes());
+            if config.wnear_balance > 0 {
+                wnear
+                    .deposit_to_evm(&engine, &funder, address, config.wnear_balance)
+                    .await?;
+            }
+            if config.eth_balance != Wei::zero() {
+                engine.mint_account(address, 0, config.eth_balance).await?;
+            }
+            users.push(FixtureUser {
+                name: config.name,
+                account,
+                address,
+            });
+        }
+
+        Ok(AuroraFixture {
+            worker,
+            engine,
+            wnear,
+            users,
+            _node: node,
+        })
+    }
+}
+
+impl AuroraFixture {
+    pub fn builder() -> AuroraFixtureBuilder {
+        AuroraFixtureBuilder::default()
+    }
+
+    /// Sets up a fixture with the given users, all with the default balances.
+    pub async fn new(user_names: &[&str]) -> anyhow::Result<Self> {
+        user_names
+            .iter()
+            .fold(Self::builder(), |builder, name| builder.user(name))
+            .build()
+            .await
+    }
+
+    /// Returns the user with the given name. Panics if there is no such user.
+    pub fn user(&self, name: &str) -> &FixtureUser {
+        self.users
+            .iter()
+            .find(|user| user.name == name)
+            .unwrap_or_else(|| panic!("Fixture has no user named {name:?}"))
+    }
+
+    pub fn users(&self) -> &[FixtureUser] {
+        &self.users
+    }
+
+    /// Captures the engine, wNEAR and user accounts. See `FixtureSnapshot`.
+    pub async fn snapshot(&self) -> anyhow::Result<FixtureSnapshot> {
+        let mut account_ids = vec![
+            self.engine.inner.id().clone(),
+            self.wnear.inner.id().clone(),
+        ];
+        account_ids.extend(self.users.iter().map(|user| user.account.id().clone()));
+        let sandbox = SandboxSnapshot::capture(&self.worker, &account_ids).await?;
+
+        Ok(FixtureSnapshot {
+            sandbox,
+            engine: SnapshotAccount::new(self.engine.inner.as_account()),
+            wnear: SnapshotAccount::new(self.wnear.inner.as_account()),
+            wnear_token: (
+                self.wnear.aurora_token.abi.clone(),
+                self.wnear.aurora_token.address,
+            ),
+            users: self
+                .users
+                .iter()
+                .map(|user| {
+                    (
+                        user.name.clone(),
+                        SnapshotAccount::new(&user.account),
+                        user.address,
+                    )
+                })
+                .collect(),
+        })
+    }
+
+    /// Starts a new sandbox containing the accounts of the snapshot.
+    pub async fn from_snapshot(snapshot: &FixtureSnapshot) -> anyhow::Result<Self> {
+        let (worker, node) = start_sandbox().await?;
+        snapshot.sandbox.restore(&worker).await?;
+
+        let engine = AuroraEngine {
+            inner: snapshot.engine.contract(&worker),
+        };
+        let (abi, address) = snapshot.wnear_token.clone();
+        let wnear = Wnear {
+            inner: snapshot.wnear.contract(&worker),
+            aurora_token: ERC20 { abi, address },
+        };
+        let users = snapshot
+            .users
+            .iter()
+            .map(|(name, account, address)| FixtureUser {
+                name: name.clone(),
+                account: account.account(&worker),
+                address: *address,
+            })
+            .collect();
+
+        Ok(Self {
+            worker,
+            engine,
+            wnear,
+            users,
+            _node: node,
+        })
+    }
+
+    /// Approves `spender` (e.g. a Solidity contract) to spend the user's wNEAR inside the EVM.
+    pub async fn approve_wnear(
+        &self,
+        user_name: &str,
+        spender: Address,
+        amount: U256,
+    ) -> anyhow::Result<CallOutcome> {
+        let user = self.user(user_name);
+        let input = self
+            .wnear
+            .aurora_token
+            .create_approve_call_bytes(spender, amount);
+        let outcome = self
+            .engine
+            .call_evm_contract_with_outcome(
+                &user.account,
+                self.wnear.aurora_token.address,
+                input,
+                Wei::zero(),
+            )
+            .await?;
+        outcome.output()?;
+        Ok(outcome)
+    }
+}
@@ -0,0 +1,155 @@
+//! Collects the gas used by the transactions of a test, so that gas regressions of the
+//! contracts under test can be tracked.
+
+use crate::aurora_engine::outcome::{CallOutcome, NearGasOutcome};
+use std::{fmt::Write, path::Path};
+
+/// If set, `GasReport::save` writes the report of each test to `{dir}/{test_name}.json`.
+const REPORT_DIR_ENV_VAR: &str = "AURORA_SDK_GAS_REPORT_DIR";
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ReceiptGasEntry {
+    pub executor_id: String,
+    pub gas_burnt: u64,
+    pub tokens_burnt: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GasReportEntry {
+    pub label: String,
+    pub near_gas_burnt: u64,
+    /// Tokens burnt in yoctoNEAR.
+    pub near_tokens_burnt: u128,
+    /// `None` for transactions which are not EVM transactions.
+    pub evm_gas_used: Option<u64>,
+    pub receipts: Vec<ReceiptGasEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct GasReport {
+    pub test_name: String,
+    pub entries: Vec<GasReportEntry>,
+}
+
+impl GasReport {
+    pub fn new(test_name: &str) -> Self {
+        Self {
+            test_name: test_name.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, label: &str, outcome: &CallOutcome) {
+        self.push(label, &outcome.near, Some(outcome.evm_gas_used()));
+    }
+
+    /// Records a plain NEAR transaction (e.g. a NEP-141 transfer).
+    pub fn record_near(&mut self, label: &str, outcome: &NearGasOutcome) {
+        self.push(label, outcome, None);
+    }
+
+    fn push(&mut self, label: &str, outcome: &NearGasOutcome, evm_gas_used: Option<u64>) {
+        self.entries.push(GasReportEntry {
+            label: label.into(),
+            near_gas_burnt: outcome.total_gas_burnt.as_gas(),
+            near_tokens_burnt: outcome.total_tokens_burnt.as_yoctonear(),
+            evm_gas_used,
+            receipts: outcome
+                .receipts
+                .iter()
+                .map(|receipt| ReceiptGasEntry {
+                    executor_id: receipt.executor_id.to_string(),
+                    gas_burnt: receipt.gas_burnt.as_gas(),
+                    tokens_burnt: receipt.tokens_burnt.as_yoctonear(),
+                })
+                .collect(),
+        });
+    }
+
+    /// Formats the report as a plain text table with one row per recorded transaction.
+    pub fn to_table(&self) -> String {
+        let label_width = self
+            .entries
+            .iter()
+            .map(|entry| entry.label.len())
+            .chain(Some("label".len()))
+            .max()
+            .unwrap_or_default();
+        let mut table = format!("{}\n", self.test_name);
+        // Writing to a `String` cannot fail, so the results are ignored.
+        let _ = writeln!(
+            table,
+            "{:label_width$}  {:>12}  {:>28}  {:>10}  {:>8}",
+            "label", "NEAR Tgas", "tokens burnt (yoctoNEAR)", "EVM gas", "receipts"
+        );
+        for entry in &self.entries {
+            let evm_gas_used = entry
+                .evm_gas_used
+                .map_or_else(|| "-".into(), |gas| gas.to_string());
+            let _ = writeln!(
+                table,
+                "{:label_width$}  {:>12.3}  {:>28}  {:>10}  {:>8}",
+                entry.label,
+                entry.near_gas_burnt as f64 / 1e12,
+                entry.near_tokens_burnt,
+                evm_gas_used,
+                entry.receipts.len(),
+            );
+        }
+        table
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes the report to the given file, as JSON if the file has a `.json` extension and
+    /// as a table otherwise.
+    pub async fn write<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = if path.extension().map_or(false, |ext| ext == "json") {
+            self.to_json()?
+        } else {
+            self.to_table()
+        };
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(path, contents).await?;
+        Ok(())
+    }
+
+    /// Writes the report to the directory given by the `AURORA_SDK_GAS_REPORT_DIR`
+    /// environment variable, if it is set. Call this at the end of a test.
+    pub async fn save(&self) -> anyhow::Result<()> {
+        match std::env::var_os(REPORT_DIR_ENV_VAR) {
+            Some(dir) => {
+                let file_name = format!("{}.json", self.test_name.replace("::", "-"));
+                self.write(Path::new(&dir).join(file_name)).await
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn test_gas_report_table() {
+    let mut report = GasReport::new("test_transfer");
+    report.entries.push(GasReportEntry {
+        label: "transfer".into(),
+        near_gas_burnt: 4_500_000_000_000,
+        near_tokens_burnt: 450_000_000_000_000_000_000,
+        evm_gas_used: Some(51_000),
+        receipts: Vec::new(),
+    });
+    let table = report.to_table();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "test_transfer");
+    assert!(lines[2].starts_with("transfer"));
+    assert!(lines[2].contains("4.500"));
+    assert!(lines[2].contains("51000"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+    assert_eq!(json["entries"][0]["evm_gas_used"], 51_000);
+}
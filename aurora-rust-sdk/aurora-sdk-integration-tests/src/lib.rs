@@ -4,8 +4,16 @@ pub use ethabi;
 pub use tokio;
 pub use workspaces;
 
+#[cfg(feature = "macros")]
+pub use aurora_sdk_integration_tests_macros::aurora_test;
+
 pub mod aurora_engine;
+pub mod eth_connector;
+pub mod eth_rpc;
+pub mod fixture;
+pub mod gas_report;
 pub mod nep141;
+pub mod snapshot;
 pub mod utils;
 pub mod wnear;
 
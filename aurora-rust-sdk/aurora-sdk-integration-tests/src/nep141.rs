@@ -1,4 +1,14 @@
-use workspaces::{Account, AccountId};
+use crate::{
+    aurora_engine::NearGasOutcome,
+    utils::cargo::{self, BuildOptions},
+};
+use serde::{Deserialize, Deserializer};
+use workspaces::{network::Sandbox, types::NearToken, Account, AccountId, Contract, Worker};
+
+/// Source of the bundled NEP-141 token contract. Besides the standard fungible token API it
+/// has `mint(account_id, amount)` and `burn(account_id, amount)` methods which anyone can call.
+const TEST_TOKEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/res/test-token");
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
 
 pub async fn ft_balance_of(
     viewer: &Account,
@@ -17,3 +27,220 @@ pub async fn ft_balance_of(
 pub struct AccountIdArgs<'a> {
     pub account_id: &'a AccountId,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StorageBalance {
+    #[serde(deserialize_with = "deserialize_u128")]
+    pub total: u128,
+    #[serde(deserialize_with = "deserialize_u128")]
+    pub available: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FungibleTokenMetadata {
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    pub icon: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<String>,
+    pub decimals: u8,
+}
+
+/// A handle to a NEP-141 fungible token contract.
+pub struct Nep141 {
+    pub inner: Contract,
+}
+
+impl Nep141 {
+    /// Builds (see `utils::cargo::build_contract_with_options`) and deploys the bundled
+    /// test token. Building requires the `wasm32-unknown-unknown` target to be installed;
+    /// the Wasm is cached, so only the first call is slow.
+    pub async fn deploy(
+        worker: &Worker<Sandbox>,
+        name: &str,
+        symbol: &str,
+        decimals: u8,
+    ) -> anyhow::Result<Self> {
+        let wasm =
+            cargo::build_contract_with_options(TEST_TOKEN_DIR, &BuildOptions::default()).await?;
+        let contract = worker.dev_deploy(&wasm).await?;
+        contract
+            .call("new")
+            .args_json(serde_json::json!({
+                "name": name,
+                "symbol": symbol,
+                "decimals": decimals,
+            }))
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(Self { inner: contract })
+    }
+
+    /// A handle to a token contract which is already deployed.
+    pub fn at(contract: Contract) -> Self {
+        Self { inner: contract }
+    }
+
+    pub fn id(&self) -> &AccountId {
+        self.inner.id()
+    }
+
+    /// Mints tokens with the `mint` method of the bundled test token. The account must be
+    /// registered (see `storage_deposit`).
+    pub async fn mint(
+        &self,
+        account_id: &AccountId,
+        amount: u128,
+    ) -> anyhow::Result<NearGasOutcome> {
+        let outcome = self
+            .inner
+            .call("mint")
+            .args_json(serde_json::json!({
+                "account_id": account_id,
+                "amount": amount.to_string(),
+            }))
+            .transact()
+            .await?;
+        NearGasOutcome::from_successful_execution(outcome)
+    }
+
+    /// Registers `account_id` (or the caller if `None`) with the minimum storage deposit,
+    /// paid by `payer`.
+    pub async fn storage_deposit(
+        &self,
+        payer: &Account,
+        account_id: Option<&AccountId>,
+    ) -> anyhow::Result<StorageBalance> {
+        let deposit = self.storage_balance_bounds().await?.0;
+        let outcome = payer
+            .call(self.id(), "storage_deposit")
+            .args_json(serde_json::json!({ "account_id": account_id }))
+            .deposit(NearToken::from_yoctonear(deposit))
+            .transact()
+            .await?
+            .into_result()?;
+        Ok(outcome.json()?)
+    }
+
+    pub async fn storage_balance_of(
+        &self,
+        account_id: &AccountId,
+    ) -> anyhow::Result<Option<StorageBalance>> {
+        let outcome = self
+            .inner
+            .view("storage_balance_of")
+            .args_json(AccountIdArgs { account_id })
+            .await?;
+        Ok(outcome.json()?)
+    }
+
+    /// Returns the minimum and maximum (if any) storage deposit.
+    pub async fn storage_balance_bounds(&self) -> anyhow::Result<(u128, Option<u128>)> {
+        #[derive(Deserialize)]
+        struct Bounds {
+            min: String,
+            max: Option<String>,
+        }
+
+        let outcome = self.inner.view("storage_balance_bounds").await?;
+        let bounds: Bounds = outcome.json()?;
+        let max = bounds.max.map(|max| max.parse::<u128>()).transpose()?;
+        Ok((bounds.min.parse()?, max))
+    }
+
+    pub async fn ft_transfer(
+        &self,
+        sender: &Account,
+        receiver_id: &AccountId,
+        amount: u128,
+        memo: Option<&str>,
+    ) -> anyhow::Result<NearGasOutcome> {
+        let outcome = sender
+            .call(self.id(), "ft_transfer")
+            .args_json(serde_json::json!({
+                "receiver_id": receiver_id,
+                "amount": amount.to_string(),
+                "memo": memo,
+            }))
+            .deposit(ONE_YOCTO)
+            .max_gas()
+            .transact()
+            .await?;
+        NearGasOutcome::from_successful_execution(outcome)
+    }
+
+    /// Returns the amount which the receiver used, i.e. the amount which was not refunded.
+    pub async fn ft_transfer_call(
+        &self,
+        sender: &Account,
+        receiver_id: &AccountId,
+        amount: u128,
+        msg: &str,
+    ) -> anyhow::Result<u128> {
+        let (used_amount, _) = self
+            .ft_transfer_call_outcome(sender, receiver_id, amount, msg)
+            .await?;
+        Ok(used_amount)
+    }
+
+    /// Same as `ft_transfer_call`, but also returns the NEAR gas burnt by the transfer and
+    /// the receiver's `ft_on_transfer`.
+    pub async fn ft_transfer_call_outcome(
+        &self,
+        sender: &Account,
+        receiver_id: &AccountId,
+        amount: u128,
+        msg: &str,
+    ) -> anyhow::Result<(u128, NearGasOutcome)> {
+        let outcome = sender
+            .call(self.id(), "ft_transfer_call")
+            .args_json(serde_json::json!({
+                "receiver_id": receiver_id,
+                "amount": amount.to_string(),
+                "msg": msg,
+            }))
+            .deposit(ONE_YOCTO)
+            .max_gas()
+            .transact()
+            .await?;
+        let near = NearGasOutcome::from_execution(&outcome);
+        let used_amount: String = outcome.into_result()?.json()?;
+        Ok((used_amount.parse()?, near))
+    }
+
+    pub async fn ft_balance_of(&self, account_id: &AccountId) -> anyhow::Result<u128> {
+        ft_balance_of(self.inner.as_account(), self.id(), account_id).await
+    }
+
+    pub async fn ft_total_supply(&self) -> anyhow::Result<u128> {
+        let outcome = self.inner.view("ft_total_supply").await?;
+        let result: String = outcome.json()?;
+        Ok(result.parse()?)
+    }
+
+    pub async fn ft_metadata(&self) -> anyhow::Result<FungibleTokenMetadata> {
+        let outcome = self.inner.view("ft_metadata").await?;
+        Ok(outcome.json()?)
+    }
+}
+
+/// NEP-141 contracts encode `u128` values as JSON strings.
+fn deserialize_u128<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(serde::de::Error::custom)
+}
+
+#[test]
+fn test_deserialize_storage_balance() {
+    let balance: StorageBalance =
+        serde_json::from_str(r#"{"total":"1250000000000000000000","available":"0"}"#).unwrap();
+    assert_eq!(
+        balance,
+        StorageBalance {
+            total: 1_250_000_000_000_000_000_000,
+            available: 0,
+        }
+    );
+}
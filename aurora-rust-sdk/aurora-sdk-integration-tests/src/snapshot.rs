@@ -0,0 +1,197 @@
+//! Capturing the state of sandbox accounts and patching it into another sandbox, so that
+//! expensive setup (e.g. deploying the engine) only needs to run once per test suite.
+//!
+//! The RPC node refuses to return the state of accounts using more storage than its
+//! `trie_viewer_state_size_limit` (50 kB by default, which an engine with a few contracts
+//! exceeds), so accounts can only be captured from a sandbox started with `start_sandbox`.
+
+use crate::utils::process;
+use std::{collections::HashMap, process::Stdio};
+use tempfile::TempDir;
+use tokio::process::{Child, Command};
+use workspaces::{
+    network::{Sandbox, ValidatorKey},
+    types::{AccessKey, AccessKeyInfo, NearToken},
+    AccountId, CryptoHash, Worker,
+};
+
+/// Same as `workspaces`: a large payload size allows patching large states.
+const JSON_PAYLOAD_MAX_SIZE: u64 = 1024 * 1024 * 1024;
+const MAX_OPEN_FILES: u64 = 3000;
+/// If set to anything but `0`, the logs of sandbox nodes are printed (as in `workspaces`).
+const ENABLE_SANDBOX_LOG_ENV_VAR: &str = "NEAR_ENABLE_SANDBOX_LOG";
+
+/// A sandbox node started with `start_sandbox`. The node is stopped and its home directory
+/// deleted when this is dropped, so it must outlive the workers connected to it.
+#[derive(Debug)]
+pub struct SandboxNode {
+    // Killed on drop, before the home directory is deleted.
+    _process: Child,
+    home_dir: TempDir,
+}
+
+impl SandboxNode {
+    pub fn home_dir(&self) -> &std::path::Path {
+        self.home_dir.path()
+    }
+}
+
+/// Starts a sandbox node which, unlike the ones started by `workspaces::sandbox`, returns
+/// the state of accounts of any size, so that they can be captured with
+/// `SandboxSnapshot::capture`. Returns a worker connected to the node.
+pub async fn start_sandbox() -> anyhow::Result<(Worker<Sandbox>, SandboxNode)> {
+    // Installing the binary (on first use) blocks the thread.
+    let bin_path = tokio::task::spawn_blocking(near_sandbox_utils::ensure_sandbox_bin).await??;
+    let home_dir = tempfile::tempdir()?;
+    let home_dir_arg = home_dir.path().as_os_str();
+    process::run(
+        Command::new(&bin_path)
+            .arg("--home")
+            .arg(home_dir_arg)
+            .args(["init", "--fast"]),
+    )
+    .await?;
+
+    let config_path = home_dir.path().join("config.json");
+    let mut config: serde_json::Value =
+        serde_json::from_slice(&tokio::fs::read(&config_path).await?)?;
+    config["trie_viewer_state_size_limit"] = serde_json::Value::Null;
+    config["rpc"]["limits_config"]["json_payload_max_size"] = JSON_PAYLOAD_MAX_SIZE.into();
+    config["store"]["max_open_files"] = MAX_OPEN_FILES.into();
+    tokio::fs::write(&config_path, serde_json::to_vec(&config)?).await?;
+
+    let rpc_addr = format!(
+        "127.0.0.1:{}",
+        workspaces::network::pick_unused_port().await?
+    );
+    let network_addr = format!(
+        "127.0.0.1:{}",
+        workspaces::network::pick_unused_port().await?
+    );
+    let show_logs = std::env::var(ENABLE_SANDBOX_LOG_ENV_VAR).map_or(false, |v| v != "0");
+    let output = || {
+        if show_logs {
+            Stdio::inherit()
+        } else {
+            Stdio::null()
+        }
+    };
+    let process = Command::new(&bin_path)
+        .arg("--home")
+        .arg(home_dir_arg)
+        .args([
+            "run",
+            "--rpc-addr",
+            &rpc_addr,
+            "--network-addr",
+            &network_addr,
+        ])
+        .stdout(output())
+        .stderr(output())
+        .kill_on_drop(true)
+        .spawn()?;
+    let node = SandboxNode {
+        _process: process,
+        home_dir,
+    };
+
+    // Waits until the RPC is ready.
+    let worker = workspaces::sandbox()
+        .rpc_addr(&format!("http://{rpc_addr}"))
+        .validator_key(ValidatorKey::HomeDir(node.home_dir().to_path_buf()))
+        .await?;
+    Ok((worker, node))
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountSnapshot {
+    pub account_id: AccountId,
+    pub balance: NearToken,
+    pub locked: NearToken,
+    pub storage_usage: u64,
+    pub code: Option<Vec<u8>>,
+    pub state: HashMap<Vec<u8>, Vec<u8>>,
+    pub access_keys: Vec<AccessKeyInfo>,
+}
+
+/// The state of a set of accounts. A snapshot only contains plain data, so it can be
+/// captured once (e.g. in a `tokio::sync::OnceCell`) and restored by many tests.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxSnapshot {
+    pub accounts: Vec<AccountSnapshot>,
+}
+
+impl SandboxSnapshot {
+    /// Captures the accounts (details, code, contract state and access keys). The worker
+    /// should be connected to a node started with `start_sandbox` (see the module docs).
+    pub async fn capture(
+        worker: &Worker<Sandbox>,
+        account_ids: &[AccountId],
+    ) -> anyhow::Result<Self> {
+        let mut accounts = Vec::with_capacity(account_ids.len());
+        for account_id in account_ids {
+            let details = worker.view_account(account_id).await?;
+            let code = if details.code_hash == CryptoHash::default() {
+                None
+            } else {
+                Some(worker.view_code(account_id).await?)
+            };
+            let state = worker.view_state(account_id).await.map_err(|e| {
+                anyhow::Error::new(e).context(format!(
+                    "Failed to read the state of {account_id} ({} bytes of storage). The RPC \
+                     node refuses to return state larger than its \
+                     `trie_viewer_state_size_limit`; capture snapshots from a sandbox started \
+                     with `snapshot::start_sandbox`",
+                    details.storage_usage
+                ))
+            })?;
+            let access_keys = worker.view_access_keys(account_id).await?;
+            accounts.push(AccountSnapshot {
+                account_id: account_id.clone(),
+                balance: details.balance,
+                locked: details.locked,
+                storage_usage: details.storage_usage,
+                code,
+                state,
+                access_keys,
+            });
+        }
+        Ok(Self { accounts })
+    }
+
+    /// Patches the captured accounts into the worker. This is meant for a fresh sandbox:
+    /// patching can only add or overwrite contract state, so keys written to an account
+    /// after the snapshot was taken are not removed.
+    pub async fn restore(&self, worker: &Worker<Sandbox>) -> anyhow::Result<()> {
+        for account in &self.accounts {
+            let details = workspaces::AccountDetailsPatch::default()
+                .balance(account.balance)
+                .locked(account.locked)
+                .storage_usage(account.storage_usage);
+            // Nonces are reset because transactions in the new sandbox must use nonces
+            // derived from its (lower) block height.
+            let access_keys = account.access_keys.iter().map(|info| {
+                let access_key = AccessKey {
+                    nonce: 0,
+                    ..info.access_key.clone()
+                };
+                (info.public_key.clone(), access_key)
+            });
+            let mut patch = worker
+                .patch(&account.account_id)
+                .account(details)
+                .access_keys(access_keys)
+                .states(
+                    account
+                        .state
+                        .iter()
+                        .map(|(key, value)| (key.as_slice(), value.as_slice())),
+                );
+            if let Some(code) = &account.code {
+                patch = patch.code(code);
+            }
+            patch.transact().await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,526 @@
+//! Tests of the entry points of `AuroraSdk.sol`, called through the `AuroraSdkTestHelper`
+//! contract of the Solidity SDK (`aurora-solidity-sdk/test/AuroraSdkTestHelper.sol`).
+
+use crate::{
+    aurora_engine::{self, AuroraEngine, ContractInput},
+    fixture::AuroraFixture,
+    nep141::Nep141,
+    utils::{ethabi::DeployedContract, forge::AuroraSdkDeployment},
+};
+use aurora_engine_sdk::types::near_account_to_evm_address;
+use aurora_engine_types::{
+    borsh::BorshSerialize,
+    parameters::{
+        engine::{CallArgs, FunctionCallArgsV2},
+        CrossContractCallArgs, NearPromise, PromiseArgs, PromiseCreateArgs, SimpleNearPromise,
+    },
+    types::{Address, NearGas, Wei, Yocto},
+    U256,
+};
+use ethabi::Token;
+use serde_json::json;
+use workspaces::{Account, AccountId};
+
+pub(super) const AURORA_SOLIDITY_SDK_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../../aurora-solidity-sdk");
+const NEAR_GAS: u64 = 10_000_000_000_000;
+const CALLBACK_GAS: u64 = 100_000_000_000_000;
+const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+/// `PromiseResultStatus.Successful` and `PromiseResultStatus.Failed`.
+const SUCCESSFUL: u8 = 1;
+const FAILED: u8 = 2;
+
+struct TestHelper {
+    contract: DeployedContract,
+    engine: AuroraEngine,
+}
+
+impl TestHelper {
+    async fn deploy(fixture: &AuroraFixture) -> anyhow::Result<Self> {
+        let aurora_sdk = AuroraSdkDeployment::get_or_deploy(
+            AURORA_SOLIDITY_SDK_PATH,
+            &fixture.worker,
+            &fixture.engine,
+        )
+        .await?;
+        let contract = aurora_sdk
+            .build_and_deploy_linked(
+                AURORA_SOLIDITY_SDK_PATH,
+                &["out", "AuroraSdkTestHelper.sol", "AuroraSdkTestHelper.json"],
+                &[Token::Address(fixture.wnear.aurora_token.address.raw())],
+            )
+            .await?;
+        Ok(Self {
+            contract,
+            engine: fixture.engine.clone(),
+        })
+    }
+
+    fn address(&self) -> Address {
+        self.contract.address
+    }
+
+    /// Calls the function and returns its return values.
+    async fn call(
+        &self,
+        account: &Account,
+        function: &str,
+        args: &[Token],
+    ) -> anyhow::Result<Vec<Token>> {
+        let input = self
+            .contract
+            .try_create_call_method_bytes_with_args(function, args)?;
+        let output = self
+            .engine
+            .try_call_evm_contract_with(account, self.address(), ContractInput(input), Wei::zero())
+            .await?;
+        Ok(self
+            .contract
+            .abi
+            .function(function)?
+            .decode_output(&output)?)
+    }
+
+    /// Calls a function which returns a single value.
+    async fn call_for_value(
+        &self,
+        account: &Account,
+        function: &str,
+        args: &[Token],
+    ) -> anyhow::Result<Token> {
+        self.call(account, function, args)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::Error::msg(format!("`{function}` returned no value")))
+    }
+
+    async fn view(&self, function: &str, args: &[Token]) -> anyhow::Result<Vec<Token>> {
+        let input = self
+            .contract
+            .try_create_call_method_bytes_with_args(function, args)?;
+        let status = self
+            .engine
+            .view_evm_contract(self.address(), ContractInput(input), None, Wei::zero())
+            .await?;
+        let output = aurora_engine::unwrap_success(status)?;
+        Ok(self
+            .contract
+            .abi
+            .function(function)?
+            .decode_output(&output)?)
+    }
+
+    /// The promise results recorded by the last `recordPromiseResults` callback.
+    async fn promise_results(&self) -> anyhow::Result<Vec<(u8, Vec<u8>)>> {
+        let count = self.view("promiseResultCount", &[]).await?.pop();
+        let count = count.and_then(Token::into_uint).unwrap_or_default();
+        let mut results = Vec::new();
+        for index in 0..count.as_u64() {
+            let mut result = self
+                .view("promiseResultAt", &[Token::Uint(index.into())])
+                .await?
+                .into_iter();
+            match (result.next(), result.next()) {
+                (Some(Token::Uint(status)), Some(Token::Bytes(output))) => {
+                    results.push((status.as_u32() as u8, output))
+                }
+                _ => return Err(anyhow::Error::msg("Unexpected `promiseResultAt` output")),
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// The arguments of `transactCall` and `lazyTransactCall`.
+fn call_args(
+    target: &AccountId,
+    method: &str,
+    args: serde_json::Value,
+    balance: u128,
+) -> [Token; 5] {
+    [
+        Token::String(target.to_string()),
+        Token::String(method.into()),
+        Token::Bytes(serde_json::to_vec(&args).unwrap()),
+        Token::Uint(balance.into()),
+        Token::Uint(NEAR_GAS.into()),
+    ]
+}
+
+/// The arguments of `transactCallWithCallback` and `lazyTransactCallWithCallback`.
+fn call_with_callback_args(
+    target: &AccountId,
+    method: &str,
+    args: serde_json::Value,
+) -> [Token; 5] {
+    [
+        Token::String(target.to_string()),
+        Token::String(method.into()),
+        Token::Bytes(serde_json::to_vec(&args).unwrap()),
+        Token::Uint(NEAR_GAS.into()),
+        Token::Uint(CALLBACK_GAS.into()),
+    ]
+}
+
+fn promise_create_args(target: &str, method: &str, args: Vec<u8>, gas: u64) -> PromiseCreateArgs {
+    PromiseCreateArgs {
+        // Unwrap is safe because the tests only use valid account IDs.
+        target_account_id: target.parse().unwrap(),
+        method: method.into(),
+        args,
+        attached_balance: Yocto::new(0),
+        attached_gas: NearGas::new(gas),
+    }
+}
+
+#[tokio::test]
+async fn test_aurora_sdk_account_ids() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let helper = TestHelper::deploy(&fixture).await.unwrap();
+
+    let current_account_id = helper
+        .call_for_value(&alice.account, "currentAccountId", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        current_account_id,
+        Token::String(engine.inner.id().to_string())
+    );
+
+    let predecessor_account_id = helper
+        .call_for_value(&alice.account, "predecessorAccountId", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        predecessor_account_id,
+        Token::String(alice.account.id().to_string())
+    );
+
+    let representative = helper
+        .call_for_value(
+            &alice.account,
+            "nearRepresentative",
+            &[Token::Address(alice.address.raw())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        representative,
+        Token::String(
+            engine
+                .xcc_sub_account_id(alice.address)
+                .unwrap()
+                .to_string()
+        )
+    );
+
+    let sub_account = helper
+        .view(
+            "addressSubAccount",
+            &[
+                Token::Address(alice.address.raw()),
+                Token::String("example.near".into()),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        sub_account,
+        [Token::String(format!(
+            "{}.example.near",
+            alice.address.encode()
+        ))]
+    );
+
+    for account_id in ["nearCrossContractCall", alice.account.id().as_str()] {
+        let address = helper
+            .view("implicitAuroraAddress", &[Token::String(account_id.into())])
+            .await
+            .unwrap();
+        let expected = near_account_to_evm_address(account_id.as_bytes());
+        assert_eq!(address, [Token::Address(expected.raw())]);
+    }
+
+    let implicit_address = helper
+        .call_for_value(
+            &alice.account,
+            "nearRepresentitiveImplicitAddress",
+            &[Token::Address(helper.address().raw())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        implicit_address,
+        Token::Address(engine.xcc_implicit_address(helper.address()).unwrap().raw())
+    );
+}
+
+#[tokio::test]
+async fn test_aurora_sdk_transact() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let helper = TestHelper::deploy(&fixture).await.unwrap();
+    let token = Nep141::deploy(&fixture.worker, "Test token", "TEST", 18)
+        .await
+        .unwrap();
+    let sub_account_id = engine.xcc_sub_account_id(helper.address()).unwrap();
+    token.storage_deposit(&alice.account, None).await.unwrap();
+    token
+        .storage_deposit(&alice.account, Some(&sub_account_id))
+        .await
+        .unwrap();
+    fixture
+        .approve_wnear("alice", helper.address(), U256::MAX)
+        .await
+        .unwrap();
+
+    // The first call creates the XCC sub-account, for which the caller pays 2 wNEAR.
+    let wnear_before = engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    let mint_args = json!({ "account_id": alice.account.id(), "amount": "100" });
+    helper
+        .call(
+            &alice.account,
+            "transactCall",
+            &call_args(token.id(), "mint", mint_args, 0),
+        )
+        .await
+        .unwrap();
+    assert_eq!(token.ft_balance_of(alice.account.id()).await.unwrap(), 100);
+    let wnear_after = engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(wnear_before - wnear_after, U256::from(2 * ONE_NEAR));
+    assert!(engine
+        .xcc_sub_account_exists(&fixture.worker, helper.address())
+        .await
+        .unwrap());
+    assert!(
+        engine
+            .xcc_router_version(&fixture.worker, helper.address())
+            .await
+            .unwrap()
+            > 0
+    );
+
+    // `then` with a callback into the EVM (`auroraCall`), which reads the result.
+    let balance_args = json!({ "account_id": alice.account.id() });
+    helper
+        .call(
+            &alice.account,
+            "transactCallWithCallback",
+            &call_with_callback_args(token.id(), "ft_balance_of", balance_args.clone()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        helper.promise_results().await.unwrap(),
+        [(SUCCESSFUL, b"\"100\"".to_vec())]
+    );
+    helper
+        .call(
+            &alice.account,
+            "transactCallWithCallback",
+            &call_with_callback_args(token.id(), "no_such_method", json!({})),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        helper.promise_results().await.unwrap(),
+        [(FAILED, Vec::new())]
+    );
+
+    // `auroraCall` is executed by the engine on behalf of the XCC sub-account.
+    let record_predecessor = helper
+        .contract
+        .create_call_method_bytes_without_args("recordPredecessor");
+    helper
+        .call(
+            &alice.account,
+            "transactAuroraCall",
+            &[
+                Token::Bytes(record_predecessor),
+                Token::Uint(CALLBACK_GAS.into()),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        helper.view("lastPredecessor", &[]).await.unwrap(),
+        [Token::String(sub_account_id.to_string())]
+    );
+
+    // `promiseResult` with several results, from promises joined with `and`. The SDK has
+    // no combinator for this, so the promise is built here.
+    let record_results = helper
+        .contract
+        .create_call_method_bytes_with_args("recordPromiseResults", &[Token::Uint(2.into())]);
+    let callback_args = CallArgs::V2(FunctionCallArgsV2 {
+        contract: helper.address(),
+        value: Wei::zero().to_bytes(),
+        input: record_results,
+    });
+    let balance_args = serde_json::to_vec(&balance_args).unwrap();
+    let promise = NearPromise::Then {
+        base: Box::new(NearPromise::And(vec![
+            NearPromise::Simple(SimpleNearPromise::Create(promise_create_args(
+                token.id().as_str(),
+                "ft_balance_of",
+                balance_args,
+                NEAR_GAS,
+            ))),
+            NearPromise::Simple(SimpleNearPromise::Create(promise_create_args(
+                token.id().as_str(),
+                "no_such_method",
+                b"{}".to_vec(),
+                NEAR_GAS,
+            ))),
+        ])),
+        callback: SimpleNearPromise::Create(promise_create_args(
+            engine.inner.id().as_str(),
+            "call",
+            callback_args.try_to_vec().unwrap(),
+            CALLBACK_GAS,
+        )),
+    };
+    let xcc_args = CrossContractCallArgs::Eager(PromiseArgs::Recursive(promise));
+    // `transactRaw` cannot pay for anything: the helper holds no wNEAR, so it relies on the
+    // XCC sub-account created above and the promises attach no NEAR.
+    assert!(engine
+        .xcc_sub_account_exists(&fixture.worker, helper.address())
+        .await
+        .unwrap());
+    assert_eq!(
+        engine
+            .erc20_balance_of(&fixture.wnear.aurora_token, helper.address())
+            .await
+            .unwrap(),
+        U256::zero()
+    );
+    helper
+        .call(
+            &alice.account,
+            "transactRaw",
+            &[Token::Bytes(xcc_args.try_to_vec().unwrap())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        helper.promise_results().await.unwrap(),
+        [(SUCCESSFUL, b"\"100\"".to_vec()), (FAILED, Vec::new())]
+    );
+}
+
+#[tokio::test]
+async fn test_aurora_sdk_lazy_transact() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let helper = TestHelper::deploy(&fixture).await.unwrap();
+    let token = Nep141::deploy(&fixture.worker, "Test token", "TEST", 18)
+        .await
+        .unwrap();
+    token.storage_deposit(&alice.account, None).await.unwrap();
+    fixture
+        .approve_wnear("alice", helper.address(), U256::MAX)
+        .await
+        .unwrap();
+
+    let mint_args = json!({ "account_id": alice.account.id(), "amount": "50" });
+    helper
+        .call(
+            &alice.account,
+            "lazyTransactCall",
+            &call_args(token.id(), "mint", mint_args, 0),
+        )
+        .await
+        .unwrap();
+    // The promise is only scheduled.
+    assert_eq!(token.ft_balance_of(alice.account.id()).await.unwrap(), 0);
+    let scheduled = engine
+        .xcc_scheduled_promises(&fixture.worker, helper.address())
+        .await
+        .unwrap();
+    let nonce = match scheduled.as_slice() {
+        [(nonce, PromiseArgs::Create(args))] => {
+            assert_eq!(args.method, "mint");
+            *nonce
+        }
+        _ => panic!("Unexpected scheduled promises: {scheduled:?}"),
+    };
+    engine
+        .xcc_execute_scheduled_with(&alice.account, helper.address(), nonce)
+        .await
+        .unwrap();
+    assert_eq!(token.ft_balance_of(alice.account.id()).await.unwrap(), 50);
+
+    let balance_args = json!({ "account_id": alice.account.id() });
+    helper
+        .call(
+            &alice.account,
+            "lazyTransactCallWithCallback",
+            &call_with_callback_args(token.id(), "ft_balance_of", balance_args),
+        )
+        .await
+        .unwrap();
+    let scheduled = engine
+        .xcc_scheduled_promises(&fixture.worker, helper.address())
+        .await
+        .unwrap();
+    let nonce = match scheduled.as_slice() {
+        [(nonce, PromiseArgs::Callback(_))] => *nonce,
+        _ => panic!("Unexpected scheduled promises: {scheduled:?}"),
+    };
+    assert!(helper.promise_results().await.unwrap().is_empty());
+    engine
+        .xcc_execute_scheduled(helper.address(), nonce)
+        .await
+        .unwrap();
+    assert_eq!(
+        helper.promise_results().await.unwrap(),
+        [(SUCCESSFUL, b"\"50\"".to_vec())]
+    );
+    assert!(engine
+        .xcc_scheduled_promises(&fixture.worker, helper.address())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_aurora_sdk_get_or_deploy() {
+    let fixture = AuroraFixture::new(&[]).await.unwrap();
+    let first = AuroraSdkDeployment::get_or_deploy(
+        AURORA_SOLIDITY_SDK_PATH,
+        &fixture.worker,
+        &fixture.engine,
+    )
+    .await
+    .unwrap();
+    let second = AuroraSdkDeployment::get_or_deploy(
+        AURORA_SOLIDITY_SDK_PATH,
+        &fixture.worker,
+        &fixture.engine,
+    )
+    .await
+    .unwrap();
+    assert_eq!(first.libraries(), second.libraries());
+
+    // Another engine on the same worker gets its own libraries.
+    let engine = aurora_engine::deploy_latest(&fixture.worker).await.unwrap();
+    let other =
+        AuroraSdkDeployment::get_or_deploy(AURORA_SOLIDITY_SDK_PATH, &fixture.worker, &engine)
+            .await
+            .unwrap();
+    assert!(!engine
+        .get_code(other.aurora_sdk_lib)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,248 @@
+//! Differential tests of the borsh encoding in `Codec.sol` and `Borsh.sol` against the Rust
+//! types used by the engine. Random values are encoded both in Rust and by the deployed
+//! `Codec` library (through `aurora-solidity-sdk/test/CodecWrapper.sol`), and the results
+//! must be identical. Promise results are encoded in Rust and decoded with the parsing of
+//! `AuroraSdk.promiseResult`.
+
+use super::aurora_sdk::AURORA_SOLIDITY_SDK_PATH;
+use crate::{
+    aurora_engine::{self, AuroraEngine, ContractInput},
+    utils::{ethabi::DeployedContract, forge::AuroraSdkDeployment},
+};
+use aurora_engine_types::{
+    borsh::{BorshDeserialize, BorshSerialize},
+    parameters::{CrossContractCallArgs, PromiseArgs, PromiseCreateArgs, PromiseWithCallbackArgs},
+    types::{NearGas, PromiseResult, Wei, Yocto},
+};
+use ethabi::Token;
+use proptest::{collection::vec, prelude::*};
+use workspaces::{network::Sandbox, Worker};
+
+/// Each case is a view call on the sandbox, so fewer cases are run than proptest's default.
+const CASES: u32 = 32;
+
+struct CodecWrapper {
+    contract: DeployedContract,
+    engine: AuroraEngine,
+    // The sandbox is stopped when the worker is dropped.
+    _worker: Worker<Sandbox>,
+}
+
+impl CodecWrapper {
+    async fn deploy() -> anyhow::Result<Self> {
+        let worker = workspaces::sandbox().await?;
+        let engine = aurora_engine::deploy_latest(&worker).await?;
+        let aurora_sdk = AuroraSdkDeployment::deploy(AURORA_SOLIDITY_SDK_PATH, &engine).await?;
+        let contract = aurora_sdk
+            .build_and_deploy_linked(
+                AURORA_SOLIDITY_SDK_PATH,
+                &["out", "CodecWrapper.sol", "CodecWrapper.json"],
+                &[],
+            )
+            .await?;
+        Ok(Self {
+            contract,
+            engine,
+            _worker: worker,
+        })
+    }
+
+    async fn view(&self, function: &str, args: &[Token]) -> anyhow::Result<Vec<Token>> {
+        let input = self
+            .contract
+            .try_create_call_method_bytes_with_args(function, args)?;
+        let status = self
+            .engine
+            .view_evm_contract(
+                self.contract.address,
+                ContractInput(input),
+                None,
+                Wei::zero(),
+            )
+            .await?;
+        let output = aurora_engine::unwrap_success(status)?;
+        Ok(self
+            .contract
+            .abi
+            .function(function)?
+            .decode_output(&output)?)
+    }
+
+    async fn view_bytes(&self, function: &str, args: &[Token]) -> anyhow::Result<Vec<u8>> {
+        match self.view(function, args).await?.as_slice() {
+            [Token::Bytes(bytes)] => Ok(bytes.clone()),
+            _ => Err(anyhow::Error::msg(format!(
+                "`{function}` did not return bytes"
+            ))),
+        }
+    }
+
+    async fn encode_cross_contract_call_args(
+        &self,
+        args: &CrossContractCallArgs,
+    ) -> anyhow::Result<Vec<u8>> {
+        let (promise, lazy) = match args {
+            CrossContractCallArgs::Eager(promise) => (promise, false),
+            CrossContractCallArgs::Delayed(promise) => (promise, true),
+        };
+        let mode = Token::Uint(u8::from(lazy).into());
+        match promise {
+            PromiseArgs::Create(promise) => {
+                self.view_bytes(
+                    "encodeCrossContractCallArgs",
+                    &[promise_create_args_token(promise), mode],
+                )
+                .await
+            }
+            PromiseArgs::Callback(promise) => {
+                self.view_bytes(
+                    "encodeCrossContractCallArgsWithCallback",
+                    &[promise_with_callback_token(promise), mode],
+                )
+                .await
+            }
+            PromiseArgs::Recursive(_) => Err(anyhow::Error::msg(
+                "Codec.sol cannot encode recursive promises",
+            )),
+        }
+    }
+
+    /// Decodes the status and output of the result at `index` of a `Vec<PromiseResult>`.
+    async fn decode_promise_result(
+        &self,
+        data: Vec<u8>,
+        index: usize,
+    ) -> anyhow::Result<(u8, Vec<u8>)> {
+        let args = [Token::Bytes(data), Token::Uint(index.into())];
+        match self.view("decodePromiseResult", &args).await?.as_slice() {
+            [Token::Uint(status), Token::Bytes(output)] => {
+                Ok((status.as_u32() as u8, output.clone()))
+            }
+            _ => Err(anyhow::Error::msg(
+                "Unexpected `decodePromiseResult` output",
+            )),
+        }
+    }
+}
+
+fn promise_create_args_token(promise: &PromiseCreateArgs) -> Token {
+    Token::Tuple(vec![
+        Token::String(promise.target_account_id.to_string()),
+        Token::String(promise.method.clone()),
+        Token::Bytes(promise.args.clone()),
+        Token::Uint(promise.attached_balance.as_u128().into()),
+        Token::Uint(promise.attached_gas.as_u64().into()),
+    ])
+}
+
+fn promise_with_callback_token(promise: &PromiseWithCallbackArgs) -> Token {
+    Token::Tuple(vec![
+        promise_create_args_token(&promise.base),
+        promise_create_args_token(&promise.callback),
+    ])
+}
+
+/// The status (`PromiseResultStatus` in Solidity) and output of a promise result.
+fn expected_promise_result(result: &PromiseResult) -> (u8, Vec<u8>) {
+    match result {
+        PromiseResult::NotReady => (0, Vec::new()),
+        PromiseResult::Successful(output) => (1, output.clone()),
+        PromiseResult::Failed => (2, Vec::new()),
+    }
+}
+
+fn account_id() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z0-9]{2,20}(\\.[a-z0-9]{1,20}){0,2}",
+        // The longest valid account IDs.
+        "[a-z0-9]{64}",
+    ]
+}
+
+fn promise_create_args() -> impl Strategy<Value = PromiseCreateArgs> {
+    (
+        account_id(),
+        "\\PC{0,32}",
+        prop_oneof![Just(Vec::new()), vec(any::<u8>(), 0..512)],
+        prop_oneof![Just(0), Just(u128::MAX), any::<u128>()],
+        prop_oneof![Just(0), Just(u64::MAX), any::<u64>()],
+    )
+        .prop_map(|(target, method, args, balance, gas)| PromiseCreateArgs {
+            // Unwrap is safe because the strategy only generates valid account IDs.
+            target_account_id: target.parse().unwrap(),
+            method,
+            args,
+            attached_balance: Yocto::new(balance),
+            attached_gas: NearGas::new(gas),
+        })
+}
+
+fn cross_contract_call_args() -> impl Strategy<Value = CrossContractCallArgs> {
+    let promise = prop_oneof![
+        promise_create_args().prop_map(PromiseArgs::Create),
+        (promise_create_args(), promise_create_args()).prop_map(|(base, callback)| {
+            PromiseArgs::Callback(PromiseWithCallbackArgs { base, callback })
+        }),
+    ];
+    (promise, any::<bool>()).prop_map(|(promise, lazy)| {
+        if lazy {
+            CrossContractCallArgs::Delayed(promise)
+        } else {
+            CrossContractCallArgs::Eager(promise)
+        }
+    })
+}
+
+fn promise_result() -> impl Strategy<Value = PromiseResult> {
+    prop_oneof![
+        Just(PromiseResult::NotReady),
+        prop_oneof![Just(Vec::new()), vec(any::<u8>(), 0..512)].prop_map(PromiseResult::Successful),
+        Just(PromiseResult::Failed),
+    ]
+}
+
+#[test]
+fn test_codec_matches_borsh() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let codec = runtime.block_on(CodecWrapper::deploy()).unwrap();
+    let config = ProptestConfig::with_cases(CASES);
+
+    proptest!(config.clone(), |(promise in promise_create_args())| {
+        let token = promise_create_args_token(&promise);
+        let encoded = runtime
+            .block_on(codec.view_bytes("encodePromiseCreateArgs", &[token]))
+            .unwrap();
+        prop_assert_eq!(&encoded, &promise.try_to_vec().unwrap());
+        prop_assert_eq!(PromiseCreateArgs::try_from_slice(&encoded).unwrap(), promise);
+    });
+
+    proptest!(config.clone(), |(base in promise_create_args(), callback in promise_create_args())| {
+        let promise = PromiseWithCallbackArgs { base, callback };
+        let token = promise_with_callback_token(&promise);
+        let encoded = runtime
+            .block_on(codec.view_bytes("encodePromiseWithCallback", &[token]))
+            .unwrap();
+        prop_assert_eq!(&encoded, &promise.try_to_vec().unwrap());
+        prop_assert_eq!(PromiseWithCallbackArgs::try_from_slice(&encoded).unwrap(), promise);
+    });
+
+    proptest!(config.clone(), |(args in cross_contract_call_args())| {
+        let encoded = runtime
+            .block_on(codec.encode_cross_contract_call_args(&args))
+            .unwrap();
+        prop_assert_eq!(&encoded, &args.try_to_vec().unwrap());
+        prop_assert_eq!(CrossContractCallArgs::try_from_slice(&encoded).unwrap(), args);
+    });
+
+    proptest!(config, |(results in vec(promise_result(), 0..4))| {
+        let data = results.try_to_vec().unwrap();
+        for (index, result) in results.iter().enumerate() {
+            let decoded = runtime
+                .block_on(codec.decode_promise_result(data.clone(), index))
+                .unwrap();
+            prop_assert_eq!(decoded, expected_promise_result(result));
+        }
+        let out_of_bounds = runtime.block_on(codec.decode_promise_result(data, results.len()));
+        prop_assert!(out_of_bounds.is_err());
+    });
+}
@@ -1,8 +1,22 @@
 use crate::{
     aurora_engine::{erc20, erc20::ERC20DeployedAt, repo::AuroraEngineRepo},
+    eth_connector::EthConnector,
+    eth_rpc::EthRpc,
+    fixture::{self, AuroraFixture},
+    gas_report::GasReport,
+    nep141::Nep141,
     wnear::Wnear,
 };
-use aurora_engine_types::types::{Address, Wei};
+use aurora_engine_sdk::types::near_account_to_evm_address;
+use aurora_engine_transactions::legacy::{LegacyEthSignedTransaction, TransactionLegacy};
+use aurora_engine_types::{
+    types::{Address, Wei},
+    U256,
+};
+use serde_json::json;
+
+mod aurora_sdk;
+mod codec;
 
 #[tokio::test]
 async fn test_compile_aurora_engine() {
@@ -48,6 +62,54 @@ async fn test_deploy_erc20() {
     assert_eq!(balance, mint_amount);
 }
 
+#[tokio::test]
+async fn test_try_call_evm_contract() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let engine = crate::aurora_engine::deploy_latest(&worker).await.unwrap();
+    let constructor = erc20::Constructor::load().await.unwrap();
+    let address = engine
+        .deploy_evm_contract(constructor.create_deploy_bytes("TEST", "AAA"))
+        .await
+        .unwrap();
+    let erc20 = constructor.deployed_at(address);
+    let recipient = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    let output = engine
+        .try_call_evm_contract(
+            address,
+            erc20.create_mint_call_bytes(recipient, 100.into()),
+            Wei::zero(),
+        )
+        .await
+        .unwrap();
+    assert!(output.is_empty());
+
+    // The engine's EVM address holds no tokens, so the transfer reverts. `call_evm_contract`
+    // returns the reverted transaction, `try_call_evm_contract` returns it as an `EvmError`.
+    let message = "ERC20: transfer amount exceeds balance";
+    let result = engine
+        .call_evm_contract(
+            address,
+            erc20.create_transfer_call_bytes(recipient, 1.into()),
+            Wei::zero(),
+        )
+        .await
+        .unwrap();
+    crate::aurora_engine::assert_reverted_with(
+        crate::aurora_engine::unwrap_submit_result(result),
+        message,
+    );
+    crate::aurora_engine::assert_reverted_with(
+        engine
+            .try_call_evm_contract(
+                address,
+                erc20.create_transfer_call_bytes(recipient, 1.into()),
+                Wei::zero(),
+            )
+            .await,
+        message,
+    );
+}
+
 #[tokio::test]
 async fn test_deploy_wnear() {
     let worker = workspaces::sandbox().await.unwrap();
@@ -73,3 +135,699 @@ async fn test_deploy_wnear() {
         .unwrap();
     assert_eq!(balance, deposit_amount.into());
 }
+
+#[tokio::test]
+async fn test_eth_rpc() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let engine = crate::aurora_engine::deploy_latest(&worker).await.unwrap();
+    let address = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    engine
+        .mint_account(address, 0, Wei::new_u64(123456))
+        .await
+        .unwrap();
+    let rpc = EthRpc::new(worker, engine);
+
+    let response = rpc
+        .handle(json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_chainId", "params": [] }))
+        .await;
+    assert_eq!(response["result"], "0x4e454153");
+
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "eth_getBalance",
+            "params": [format!("0x{}", address.encode()), "latest"],
+        }))
+        .await;
+    assert_eq!(response["result"], "0x1e240");
+
+    let response = rpc
+        .handle(json!({ "jsonrpc": "2.0", "id": 3, "method": "eth_mining", "params": [] }))
+        .await;
+    assert_eq!(response["error"]["code"], -32601);
+
+    let chain_id = rpc.engine().get_chain_id().await.unwrap();
+    let secret_key = libsecp256k1::SecretKey::parse(&[0x42; 32]).unwrap();
+    let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+    let sender =
+        Address::try_from_slice(&aurora_engine_sdk::keccak(&public_key.serialize()[1..])[12..])
+            .unwrap();
+
+    // Deploy a contract which emits a log with topic 1 and returns 42 when called.
+    let deploy_tx = sign_legacy_transaction(
+        TransactionLegacy {
+            nonce: 0.into(),
+            gas_price: 0.into(),
+            gas_limit: u64::MAX.into(),
+            to: None,
+            value: Wei::zero(),
+            data: hex::decode([INIT_CODE, RUNTIME_CODE].concat()).unwrap(),
+        },
+        chain_id,
+        &secret_key,
+    );
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "eth_sendRawTransaction",
+            "params": [format!("0x{}", hex::encode(deploy_tx))],
+        }))
+        .await;
+    let deploy_hash = response["result"].clone();
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 5,
+            "method": "eth_getTransactionReceipt",
+            "params": [deploy_hash],
+        }))
+        .await;
+    let receipt = &response["result"];
+    assert_eq!(receipt["status"], "0x1");
+    assert_eq!(receipt["from"], format!("0x{}", sender.encode()));
+    let contract = receipt["contractAddress"].clone();
+    assert!(contract.is_string());
+
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 6,
+            "method": "eth_getCode",
+            "params": [contract, "latest"],
+        }))
+        .await;
+    assert_eq!(response["result"], format!("0x{RUNTIME_CODE}"));
+
+    let call_tx = sign_legacy_transaction(
+        TransactionLegacy {
+            nonce: 1.into(),
+            gas_price: 0.into(),
+            gas_limit: u64::MAX.into(),
+            // Unwrap is safe because the receipt holds a hex-encoded address.
+            to: Some(Address::decode(&contract.as_str().unwrap()[2..]).unwrap()),
+            value: Wei::zero(),
+            data: Vec::new(),
+        },
+        chain_id,
+        &secret_key,
+    );
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "eth_sendRawTransaction",
+            "params": [format!("0x{}", hex::encode(call_tx))],
+        }))
+        .await;
+    let call_hash = response["result"].clone();
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 8,
+            "method": "eth_getTransactionReceipt",
+            "params": [call_hash],
+        }))
+        .await;
+    let receipt = &response["result"];
+    assert_eq!(receipt["status"], "0x1");
+    assert_eq!(receipt["logs"].as_array().unwrap().len(), 1);
+
+    let topic = format!("0x{:064x}", 1);
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 9,
+            "method": "eth_getLogs",
+            "params": [{ "address": contract, "topics": [topic], "fromBlock": "earliest" }],
+        }))
+        .await;
+    let logs = response["result"].as_array().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0]["transactionHash"], call_hash);
+    assert_eq!(logs[0]["topics"], json!([topic]));
+    assert_eq!(logs[0]["data"], format!("0x{:064x}", 42));
+
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 10,
+            "method": "eth_getLogs",
+            "params": [{ "address": contract, "topics": [format!("0x{:064x}", 2)] }],
+        }))
+        .await;
+    assert_eq!(response["result"], json!([]));
+
+    let response = rpc
+        .handle(json!({
+            "jsonrpc": "2.0",
+            "id": 11,
+            "method": "eth_call",
+            "params": [{ "to": contract, "data": "0x" }, "latest"],
+        }))
+        .await;
+    assert_eq!(response["result"], format!("0x{:064x}", 42));
+}
+
+/// Copies `RUNTIME_CODE` (17 bytes, following these 11 bytes) into memory and returns it.
+const INIT_CODE: &str = "601180600b6000396000f3";
+/// Stores 42 in memory, emits it as a log with topic 1 (`LOG1`) and returns it.
+const RUNTIME_CODE: &str = "602a600052600160206000a160206000f3";
+
+/// Signs `transaction` with EIP-155 replay protection and returns its RLP encoding.
+fn sign_legacy_transaction(
+    transaction: TransactionLegacy,
+    chain_id: u64,
+    secret_key: &libsecp256k1::SecretKey,
+) -> Vec<u8> {
+    let mut stream = rlp::RlpStream::new();
+    transaction.rlp_append_unsigned(&mut stream, Some(chain_id));
+    let message_hash = aurora_engine_sdk::keccak(stream.as_raw());
+    let message = libsecp256k1::Message::parse_slice(message_hash.as_bytes()).unwrap();
+    let (signature, recovery_id) = libsecp256k1::sign(&message, secret_key);
+    let signed = LegacyEthSignedTransaction {
+        transaction,
+        v: u64::from(recovery_id.serialize()) + 2 * chain_id + 35,
+        r: U256::from_big_endian(&signature.r.b32()),
+        s: U256::from_big_endian(&signature.s.b32()),
+    };
+    rlp::encode(&signed).to_vec()
+}
+
+#[tokio::test]
+async fn test_engine_view_queries() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let engine = crate::aurora_engine::deploy_latest(&worker).await.unwrap();
+    let address = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    engine.mint_account(address, 7, Wei::zero()).await.unwrap();
+    assert_eq!(engine.get_nonce(address).await.unwrap(), 7.into());
+    assert_eq!(engine.get_chain_id().await.unwrap(), 1313161555);
+    assert_eq!(&engine.get_owner().await.unwrap(), engine.inner.id());
+    assert!(!engine.get_version().await.unwrap().is_empty());
+
+    let constructor = erc20::Constructor::load().await.unwrap();
+    let erc20_address = engine
+        .deploy_evm_contract(constructor.create_deploy_bytes("TEST", "AAA"))
+        .await
+        .unwrap();
+    assert!(!engine.get_code(erc20_address).await.unwrap().is_empty());
+    assert!(engine.get_code(address).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_multiple_engines_per_worker() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let (first, second) = tokio::try_join!(
+        crate::aurora_engine::deploy_latest(&worker),
+        crate::aurora_engine::deploy_latest(&worker),
+    )
+    .unwrap();
+    assert_ne!(first.inner.id(), second.inner.id());
+    // The IDs must stay short enough for `{address}.{engine}` XCC sub-accounts.
+    assert!(first.inner.id().len() + 41 <= 64);
+    assert!(second.inner.id().len() + 41 <= 64);
+
+    let address = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    let balance = Wei::new_u64(123456);
+    first.mint_account(address, 0, balance).await.unwrap();
+    assert_eq!(first.get_balance(address).await.unwrap(), balance);
+    assert_eq!(second.get_balance(address).await.unwrap(), Wei::zero());
+}
+
+#[tokio::test]
+async fn test_fixture() {
+    let eth_balance = Wei::new_u64(1_000_000);
+    let fixture = AuroraFixture::builder()
+        .user("alice")
+        .user_with_balances("bob", 0, eth_balance)
+        .build()
+        .await
+        .unwrap();
+
+    let alice = fixture.user("alice");
+    assert_eq!(alice.account.id().as_str(), "alice.test.near");
+    let balance = fixture
+        .engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, fixture::DEFAULT_WNEAR_BALANCE.into());
+
+    let bob = fixture.user("bob");
+    let balance = fixture
+        .engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, bob.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, 0.into());
+    let balance = fixture.engine.get_balance(bob.address).await.unwrap();
+    assert_eq!(balance, eth_balance);
+
+    // Balances which do not fit in a u64 are rejected instead of being truncated.
+    let too_large = Wei::new(U256::from(u64::MAX) + 1);
+    assert!(fixture
+        .engine
+        .mint_account(bob.address, 0, too_large)
+        .await
+        .is_err());
+    let balance = fixture.engine.get_balance(bob.address).await.unwrap();
+    assert_eq!(balance, eth_balance);
+}
+
+#[tokio::test]
+async fn test_fixture_snapshot() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let snapshot = fixture.snapshot().await.unwrap();
+
+    let restored = AuroraFixture::from_snapshot(&snapshot).await.unwrap();
+    let alice = restored.user("alice");
+    let balance = restored
+        .engine
+        .erc20_balance_of(&restored.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, fixture::DEFAULT_WNEAR_BALANCE.into());
+
+    // The restored accounts can still sign transactions.
+    let address = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    restored
+        .approve_wnear("alice", address, 1.into())
+        .await
+        .unwrap();
+    restored
+        .engine
+        .mint_account(address, 0, Wei::new_u64(1))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_gas_report() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let engine = crate::aurora_engine::deploy_latest(&worker).await.unwrap();
+    let constructor = erc20::Constructor::load().await.unwrap();
+    let mut report = GasReport::new("test_gas_report");
+
+    let outcome = engine
+        .deploy_evm_contract_with_outcome(
+            engine.inner.as_account(),
+            constructor.create_deploy_bytes("TEST", "AAA"),
+        )
+        .await
+        .unwrap();
+    report.record("deploy", &outcome);
+    let bytes = crate::aurora_engine::unwrap_submit_result(outcome.result).unwrap();
+    let erc20 = constructor.deployed_at(Address::try_from_slice(&bytes).unwrap());
+
+    let recipient = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    let outcome = engine
+        .call_evm_contract_with_outcome(
+            engine.inner.as_account(),
+            erc20.address,
+            erc20.create_mint_call_bytes(recipient, 100.into()),
+            Wei::zero(),
+        )
+        .await
+        .unwrap();
+    report.record("mint", &outcome);
+    // The mint emits a `Transfer` event.
+    assert_eq!(outcome.logs().len(), 1);
+    assert!(outcome.evm_gas_used() > 0);
+    assert!(outcome.near.total_gas_burnt.as_gas() > 0);
+    assert_eq!(
+        outcome.near.receipts[0].executor_id,
+        *engine.inner.as_account().id()
+    );
+
+    // The typed helpers return the outcomes of their transactions too.
+    let outcome = engine
+        .erc20_mint(&erc20, recipient, 100.into())
+        .await
+        .unwrap();
+    report.record("erc20_mint", &outcome);
+    assert_eq!(outcome.logs().len(), 1);
+    let outcome = engine
+        .mint_account(recipient, 0, Wei::new_u64(1))
+        .await
+        .unwrap();
+    report.record_near("mint_account", &outcome);
+    assert!(outcome.total_gas_burnt.as_gas() > 0);
+
+    assert_eq!(report.entries.len(), 4);
+    assert_eq!(report.entries[3].evm_gas_used, None);
+    assert!(report.to_table().contains("mint_account"));
+    report.save().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_erc20_client() {
+    let fixture = AuroraFixture::new(&["alice", "bob"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let bob = fixture.user("bob");
+    let engine_address = near_account_to_evm_address(engine.inner.id().as_bytes());
+
+    let constructor = erc20::Constructor::load().await.unwrap();
+    let address = engine
+        .deploy_evm_contract(constructor.create_deploy_bytes("TEST", "AAA"))
+        .await
+        .unwrap();
+    let token = constructor.deployed_at(address);
+    assert_eq!(engine.erc20_name(&token).await.unwrap(), "TEST");
+    assert_eq!(engine.erc20_symbol(&token).await.unwrap(), "AAA");
+    assert_eq!(engine.erc20_decimals(&token).await.unwrap(), 18);
+
+    engine
+        .erc20_mint(&token, alice.address, 1000.into())
+        .await
+        .unwrap();
+    engine
+        .erc20_transfer_with(&alice.account, &token, bob.address, 100.into())
+        .await
+        .unwrap();
+    engine
+        .erc20_approve_with(&alice.account, &token, engine_address, 200.into())
+        .await
+        .unwrap();
+    engine
+        .erc20_transfer_from(&token, alice.address, bob.address, 50.into())
+        .await
+        .unwrap();
+    let allowance = engine
+        .erc20_allowance(&token, alice.address, engine_address)
+        .await
+        .unwrap();
+    assert_eq!(allowance, 150.into());
+    engine
+        .erc20_burn_with(&alice.account, &token, 50.into())
+        .await
+        .unwrap();
+    let balance = engine
+        .erc20_balance_of(&token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, 800.into());
+    let balance = engine.erc20_balance_of(&token, bob.address).await.unwrap();
+    assert_eq!(balance, 150.into());
+    assert_eq!(engine.erc20_total_supply(&token).await.unwrap(), 950.into());
+
+    engine.erc20_pause(&token).await.unwrap();
+    assert!(engine.erc20_paused(&token).await.unwrap());
+    crate::aurora_engine::assert_reverted_with(
+        engine
+            .erc20_transfer_with(&alice.account, &token, bob.address, 1.into())
+            .await,
+        "ERC20Pausable: token transfer while paused",
+    );
+    engine.erc20_unpause(&token).await.unwrap();
+
+    let role = erc20::Role::Minter;
+    assert!(!engine
+        .erc20_has_role(&token, role, alice.address)
+        .await
+        .unwrap());
+    engine
+        .erc20_grant_role(&token, role, alice.address)
+        .await
+        .unwrap();
+    assert!(engine
+        .erc20_has_role(&token, role, alice.address)
+        .await
+        .unwrap());
+    let count = engine
+        .erc20_get_role_member_count(&token, role)
+        .await
+        .unwrap();
+    assert_eq!(count, 2.into());
+    engine
+        .erc20_mint_with(&alice.account, &token, bob.address, 1.into())
+        .await
+        .unwrap();
+    engine
+        .erc20_renounce_role_with(&alice.account, &token, role)
+        .await
+        .unwrap();
+    assert!(!engine
+        .erc20_has_role(&token, role, alice.address)
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn test_nep141() {
+    let fixture = AuroraFixture::new(&["alice", "bob"]).await.unwrap();
+    let alice = &fixture.user("alice").account;
+    let bob = &fixture.user("bob").account;
+    let token = Nep141::deploy(&fixture.worker, "Test Token", "TT", 6)
+        .await
+        .unwrap();
+
+    let metadata = token.ft_metadata().await.unwrap();
+    assert_eq!(metadata.symbol, "TT");
+    assert_eq!(metadata.decimals, 6);
+    assert!(token
+        .storage_balance_of(alice.id())
+        .await
+        .unwrap()
+        .is_none());
+    token.storage_deposit(alice, None).await.unwrap();
+    let balance = token.storage_deposit(alice, Some(bob.id())).await.unwrap();
+    assert_eq!(
+        token.storage_balance_of(bob.id()).await.unwrap(),
+        Some(balance)
+    );
+
+    token.mint(alice.id(), 1_000).await.unwrap();
+    token.ft_transfer(alice, bob.id(), 300, None).await.unwrap();
+    // Bob has no contract, so `ft_on_transfer` fails and the tokens are refunded.
+    let used = token
+        .ft_transfer_call(alice, bob.id(), 100, "")
+        .await
+        .unwrap();
+    assert_eq!(used, 0);
+    assert_eq!(token.ft_balance_of(alice.id()).await.unwrap(), 700);
+    assert_eq!(token.ft_balance_of(bob.id()).await.unwrap(), 300);
+    assert_eq!(token.ft_total_supply().await.unwrap(), 1_000);
+}
+
+#[tokio::test]
+async fn test_deposit_nep141() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let token = Nep141::deploy(&fixture.worker, "Test Token", "TT", 6)
+        .await
+        .unwrap();
+    token.storage_deposit(&alice.account, None).await.unwrap();
+    token.mint(alice.account.id(), 1_000).await.unwrap();
+    // The token must be bridged before it can be deposited.
+    let err = engine
+        .deposit_nep141(&token, &alice.account, alice.address, 400)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("is not bridged"), "{err:?}");
+    assert_eq!(
+        token.ft_balance_of(alice.account.id()).await.unwrap(),
+        1_000
+    );
+
+    engine.bridge_nep141(token.id()).await.unwrap();
+    let erc20 = engine
+        .deposit_nep141(&token, &alice.account, alice.address, 400)
+        .await
+        .unwrap();
+    let balance = engine
+        .erc20_balance_of(&erc20, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, 400.into());
+    assert_eq!(token.ft_balance_of(engine.inner.id()).await.unwrap(), 400);
+    assert_eq!(token.ft_balance_of(alice.account.id()).await.unwrap(), 600);
+}
+
+#[tokio::test]
+async fn test_withdraw_to_near() {
+    let fixture = AuroraFixture::new(&["alice", "bob"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let bob = fixture.user("bob");
+    let token = Nep141::deploy(&fixture.worker, "Test Token", "TT", 6)
+        .await
+        .unwrap();
+    engine.bridge_nep141(token.id()).await.unwrap();
+    token.storage_deposit(&alice.account, None).await.unwrap();
+    token.storage_deposit(&bob.account, None).await.unwrap();
+    token.mint(alice.account.id(), 1_000).await.unwrap();
+    let erc20 = engine
+        .deposit_nep141(&token, &alice.account, alice.address, 1_000)
+        .await
+        .unwrap();
+
+    engine
+        .erc20_withdraw_to_near_with(&alice.account, &erc20, bob.account.id(), 250)
+        .await
+        .unwrap();
+    assert_eq!(token.ft_balance_of(bob.account.id()).await.unwrap(), 250);
+    assert_eq!(token.ft_balance_of(engine.inner.id()).await.unwrap(), 750);
+    // The withdrawn ERC-20 tokens are burned, so the bridged supply matches the NEP-141
+    // tokens locked in the engine.
+    let balance = engine
+        .erc20_balance_of(&erc20, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, 750.into());
+    assert_eq!(engine.erc20_total_supply(&erc20).await.unwrap(), 750.into());
+
+    let amount = fixture::DEFAULT_WNEAR_BALANCE / 5;
+    engine
+        .unwrap_wnear_with(&alice.account, &fixture.wnear, &bob.account, amount)
+        .await
+        .unwrap();
+    let balance = engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, (fixture::DEFAULT_WNEAR_BALANCE - amount).into());
+}
+
+#[tokio::test]
+async fn test_eth_connector() {
+    let fixture = AuroraFixture::new(&["alice"]).await.unwrap();
+    let engine = &fixture.engine;
+    let alice = fixture.user("alice");
+    let custodian = Address::decode("00000000000000000000000000000000000c0570").unwrap();
+    let connector = EthConnector::deploy(&fixture.worker, engine, custodian)
+        .await
+        .unwrap();
+
+    connector
+        .deposit(engine, &alice.account, alice.account.id(), 1_000)
+        .await
+        .unwrap();
+    let supply_before = engine.ft_total_eth_supply_on_aurora().await.unwrap();
+    engine
+        .eth_ft_transfer_call(&alice.account, alice.address, 400)
+        .await
+        .unwrap();
+    assert_eq!(
+        engine.get_balance(alice.address).await.unwrap(),
+        Wei::new_u64(400)
+    );
+    let supply_after = engine.ft_total_eth_supply_on_aurora().await.unwrap();
+    assert_eq!(supply_after - supply_before, 400);
+
+    let recipient = Address::decode("000000000000000000000000000000000000000a").unwrap();
+    let result = engine
+        .withdraw_eth(&alice.account, recipient, 100)
+        .await
+        .unwrap();
+    assert_eq!(result.recipient_id, recipient);
+    assert_eq!(result.eth_custodian_address, custodian);
+    let balance = engine.ft_balance_of(alice.account.id()).await.unwrap();
+    assert_eq!(balance, 500);
+}
+
+#[tokio::test]
+async fn test_wnear_flows() {
+    const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+    let fixture = AuroraFixture::new(&["alice", "bob"]).await.unwrap();
+    let engine = &fixture.engine;
+    let wnear = &fixture.wnear;
+    let alice = fixture.user("alice");
+    let bob = fixture.user("bob");
+
+    wnear
+        .deposit_to_evm(engine, &alice.account, alice.address, ONE_NEAR)
+        .await
+        .unwrap();
+    let balance = engine
+        .erc20_balance_of(&wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, (fixture::DEFAULT_WNEAR_BALANCE + ONE_NEAR).into());
+
+    wnear
+        .near_deposit(&alice.account, 2 * ONE_NEAR)
+        .await
+        .unwrap();
+    wnear.storage_deposit(&bob.account).await.unwrap();
+    wnear
+        .ft_transfer(&alice.account, bob.account.id(), ONE_NEAR)
+        .await
+        .unwrap();
+    let near_before = bob.account.view_account().await.unwrap().balance;
+    wnear
+        .near_withdraw(&bob.account, ONE_NEAR / 2)
+        .await
+        .unwrap();
+    let near_after = bob.account.view_account().await.unwrap().balance;
+    assert!(near_after > near_before);
+    assert_eq!(
+        wnear.ft_balance_of(bob.account.id()).await.unwrap(),
+        ONE_NEAR / 2
+    );
+    // Bob has no contract, so `ft_on_transfer` fails and the tokens are refunded.
+    let used = wnear
+        .ft_transfer_call(&alice.account, bob.account.id(), ONE_NEAR / 10, "")
+        .await
+        .unwrap();
+    assert_eq!(used, 0);
+
+    wnear
+        .unwrap_from_evm(engine, &alice.account, &bob.account, ONE_NEAR / 2)
+        .await
+        .unwrap();
+    // Unwrapping to the signing account itself, which also pays for the gas.
+    let near_before = alice.account.view_account().await.unwrap().balance;
+    wnear
+        .unwrap_from_evm(engine, &alice.account, &alice.account, ONE_NEAR / 2)
+        .await
+        .unwrap();
+    let near_after = alice.account.view_account().await.unwrap().balance;
+    assert!(near_after > near_before);
+    let balance = engine
+        .erc20_balance_of(&wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, fixture::DEFAULT_WNEAR_BALANCE.into());
+}
+
+#[tokio::test]
+async fn test_xcc_sub_account() {
+    let worker = workspaces::sandbox().await.unwrap();
+    let engine = crate::aurora_engine::deploy_latest(&worker).await.unwrap();
+    let wnear = Wnear::deploy(&worker, &engine).await.unwrap();
+    let address = Address::decode("000000000000000000000000000000000000000a").unwrap();
+
+    let sub_account_id = engine.xcc_sub_account_id(address).unwrap();
+    let implicit_address = engine.xcc_implicit_address(address).unwrap();
+    assert_eq!(
+        sub_account_id.as_str(),
+        format!(
+            "000000000000000000000000000000000000000a.{}",
+            engine.inner.id()
+        )
+    );
+    assert_eq!(
+        implicit_address,
+        near_account_to_evm_address(sub_account_id.as_bytes())
+    );
+    // The sub-account is only created when the address first uses the XCC precompile.
+    assert!(!engine
+        .xcc_sub_account_exists(&worker, address)
+        .await
+        .unwrap());
+
+    let amount = 2_000_000_000_000_000_000_000_000;
+    engine
+        .fund_xcc_sub_account(&wnear, address, amount)
+        .await
+        .unwrap();
+    let balance = engine
+        .erc20_balance_of(&wnear.aurora_token, implicit_address)
+        .await
+        .unwrap();
+    assert_eq!(balance, amount.into());
+}
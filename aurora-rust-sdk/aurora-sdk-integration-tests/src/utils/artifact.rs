@@ -0,0 +1,471 @@
+//! Loader for the JSON artifacts produced by the common Solidity toolchains (Foundry,
+//! Hardhat, Truffle and `solc --standard-json`), including linking library addresses
+//! into the placeholders of unlinked bytecode.
+
+use crate::utils::ethabi::{function_signature, ContractConstructor, ContractError};
+use aurora_engine_types::types::Address;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Length (in hex characters) of a library placeholder / address in bytecode.
+const PLACEHOLDER_LEN: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactFormat {
+    Foundry,
+    Hardhat,
+    Truffle,
+    SolcStandardJson,
+}
+
+/// Byte offsets of the placeholders for a library, as reported by the compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub struct LinkOffset {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Source file -> library name -> placeholder offsets.
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, Vec<LinkOffset>>>;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Bytecode {
+    /// Hex-encoded bytecode (without `0x` prefix), possibly containing library placeholders.
+    pub object: String,
+    /// Libraries which are not linked yet.
+    pub link_references: LinkReferences,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolidityArtifact {
+    pub format: ArtifactFormat,
+    pub contract_name: Option<String>,
+    pub abi: ethabi::Contract,
+    pub bytecode: Bytecode,
+    pub deployed_bytecode: Bytecode,
+    /// Function signature (e.g. `transfer(address,uint256)`) -> hex-encoded selector.
+    pub method_identifiers: BTreeMap<String, String>,
+}
+
+impl SolidityArtifact {
+    /// Loads an artifact from the given file. `contract_name` is only needed for
+    /// `solc` standard-JSON output containing more than one contract.
+    pub fn load<P: AsRef<Path>>(
+        path: P,
+        contract_name: Option<&str>,
+    ) -> Result<Self, ContractError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|source| ContractError::Io {
+            path: path.into(),
+            source,
+        })?;
+        let value: Value =
+            serde_json::from_slice(&bytes).map_err(|source| ContractError::Json {
+                path: path.into(),
+                source,
+            })?;
+        Self::from_json(&value, contract_name).map_err(|e| e.with_path(path))
+    }
+
+    pub fn from_json(value: &Value, contract_name: Option<&str>) -> Result<Self, ContractError> {
+        if let Some(contracts) = value.get("contracts") {
+            return Self::from_standard_json_output(contracts, contract_name);
+        }
+        if value.get("evm").is_some() {
+            return Self::from_standard_json_contract(value, contract_name.map(Into::into));
+        }
+
+        let format = match value.get("bytecode") {
+            Some(Value::Object(_)) => ArtifactFormat::Foundry,
+            Some(Value::String(_))
+                if value.get("_format").is_none()
+                    && value.get("linkReferences").is_none()
+                    && ["schemaVersion", "networks", "updatedAt"]
+                        .iter()
+                        .any(|k| value.get(k).is_some()) =>
+            {
+                ArtifactFormat::Truffle
+            }
+            Some(Value::String(_)) => ArtifactFormat::Hardhat,
+            _ => return Err(invalid("unrecognized artifact format")),
+        };
+        let (bytecode, deployed_bytecode) = match format {
+            ArtifactFormat::Foundry => (
+                parse_bytecode_object(value.get("bytecode"))?,
+                parse_bytecode_object(value.get("deployedBytecode"))?,
+            ),
+            _ => (
+                parse_bytecode_str(value.get("bytecode"), value.get("linkReferences"))?,
+                parse_bytecode_str(
+                    value.get("deployedBytecode"),
+                    value.get("deployedLinkReferences"),
+                )?,
+            ),
+        };
+        let abi = parse_abi(value.get("abi"))?;
+        let method_identifiers = parse_method_identifiers(value.get("methodIdentifiers"), &abi)?;
+        let contract_name = value
+            .get("contractName")
+            .and_then(Value::as_str)
+            .map(Into::into)
+            .or_else(|| contract_name.map(Into::into));
+
+        Ok(Self {
+            format,
+            contract_name,
+            abi,
+            bytecode,
+            deployed_bytecode,
+            method_identifiers,
+        })
+    }
+
+    fn from_standard_json_output(
+        contracts: &Value,
+        contract_name: Option<&str>,
+    ) -> Result<Self, ContractError> {
+        let contracts = contracts
+            .as_object()
+            .ok_or_else(|| invalid("`contracts` must be an object"))?;
+        // Contract names may be given either as `Name` or fully qualified as `file.sol:Name`.
+        let mut candidates = Vec::new();
+        for (file, file_contracts) in contracts {
+            for (name, contract) in file_contracts.as_object().into_iter().flatten() {
+                let qualified = format!("{file}:{name}");
+                let matches = contract_name.map_or(true, |n| n == name.as_str() || n == qualified);
+                if matches {
+                    candidates.push((name, contract));
+                }
+            }
+        }
+        match candidates.as_slice() {
+            [(name, contract)] => {
+                Self::from_standard_json_contract(contract, Some(name.to_string()))
+            }
+            _ => {
+                let available = contracts
+                    .iter()
+                    .flat_map(|(file, cs)| {
+                        cs.as_object()
+                            .into_iter()
+                            .flatten()
+                            .map(move |(name, _)| format!("{file}:{name}"))
+                    })
+                    .collect();
+                Err(ContractError::ContractNotFound {
+                    path: None,
+                    name: contract_name.map(Into::into),
+                    available,
+                })
+            }
+        }
+    }
+
+    fn from_standard_json_contract(
+        contract: &Value,
+        contract_name: Option<String>,
+    ) -> Result<Self, ContractError> {
+        let evm = contract
+            .get("evm")
+            .ok_or_else(|| invalid("missing `evm` output"))?;
+        let abi = parse_abi(contract.get("abi"))?;
+        let method_identifiers = parse_method_identifiers(evm.get("methodIdentifiers"), &abi)?;
+        Ok(Self {
+            format: ArtifactFormat::SolcStandardJson,
+            contract_name,
+            abi,
+            bytecode: parse_bytecode_object(evm.get("bytecode"))?,
+            deployed_bytecode: parse_bytecode_object(evm.get("deployedBytecode"))?,
+            method_identifiers,
+        })
+    }
+
+    /// Returns the selector of the function with the given signature
+    /// (e.g. `transfer(address,uint256)`).
+    pub fn selector(&self, signature: &str) -> Option<[u8; 4]> {
+        let hex_selector = self.method_identifiers.get(&signature.replace(' ', ""))?;
+        let bytes = hex::decode(hex_selector).ok()?;
+        bytes.try_into().ok()
+    }
+
+    /// Names (`file:Library`) of the libraries which must be linked before deploying.
+    pub fn unlinked_libraries(&self) -> Vec<String> {
+        let mut result = self.bytecode.unlinked_libraries();
+        for library in self.deployed_bytecode.unlinked_libraries() {
+            if !result.contains(&library) {
+                result.push(library);
+            }
+        }
+        result
+    }
+
+    /// Links the library with the given name into both the creation and deployed bytecode.
+    /// The name can be fully qualified (`path/to/File.sol:Library`) or just `Library`,
+    /// in which case it is linked regardless of the file it is defined in.
+    pub fn link_library(&mut self, library: &str, address: Address) -> Result<(), ContractError> {
+        let linked = self.bytecode.link(library, address);
+        let deployed_linked = self.deployed_bytecode.link(library, address);
+        if linked || deployed_linked {
+            Ok(())
+        } else {
+            Err(ContractError::UnknownLibrary {
+                library: library.into(),
+                unlinked: self.unlinked_libraries(),
+            })
+        }
+    }
+
+    /// Converts the artifact into a `ContractConstructor`. All libraries must be linked.
+    pub fn into_constructor(self) -> Result<ContractConstructor, ContractError> {
+        Ok(ContractConstructor {
+            code: self.bytecode.to_bytes()?,
+            abi: self.abi,
+        })
+    }
+}
+
+impl Bytecode {
+    pub fn is_linked(&self) -> bool {
+        self.unlinked_libraries().is_empty()
+    }
+
+    /// Names (`file:Library`) of the libraries which are not linked yet.
+    pub fn unlinked_libraries(&self) -> Vec<String> {
+        self.link_references
+            .iter()
+            .flat_map(|(file, libraries)| {
+                libraries.keys().map(move |library| match file.as_str() {
+                    "" => library.clone(),
+                    file => format!("{file}:{library}"),
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces the placeholders of the given library with its address. Returns `false`
+    /// if the bytecode does not reference the library.
+    pub fn link(&mut self, library: &str, address: Address) -> bool {
+        let (file, name) = match library.rsplit_once(':') {
+            Some((file, name)) => (Some(file), name),
+            None => (None, library),
+        };
+        let address_hex = address.encode();
+        let mut linked = false;
+        for (ref_file, libraries) in self.link_references.iter_mut() {
+            if file.map_or(false, |f| f != ref_file.as_str()) {
+                continue;
+            }
+            if let Some(offsets) = libraries.remove(name) {
+                for offset in offsets {
+                    let start = 2 * offset.start;
+                    let end = start + 2 * offset.length;
+                    if end <= self.object.len() {
+                        self.object.replace_range(start..end, &address_hex);
+                    }
+                }
+                linked = true;
+            }
+        }
+        self.link_references
+            .retain(|_, libraries| !libraries.is_empty());
+
+        // Placeholders which the compiler did not report offsets for (e.g. Truffle artifacts).
+        let mut placeholders = vec![legacy_placeholder(library)];
+        if let Some(file) = file {
+            placeholders.push(hashed_placeholder(file, name));
+        }
+        for placeholder in placeholders {
+            if self.object.contains(&placeholder) {
+                self.object = self.object.replace(&placeholder, &address_hex);
+                linked = true;
+            }
+        }
+        linked
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ContractError> {
+        let mut unlinked = self.unlinked_libraries();
+        unlinked.extend(scan_placeholders(&self.object));
+        if !unlinked.is_empty() {
+            unlinked.sort();
+            unlinked.dedup();
+            return Err(ContractError::UnlinkedLibraries {
+                libraries: unlinked,
+            });
+        }
+        hex::decode(&self.object).map_err(|e| ContractError::InvalidBytecode {
+            path: None,
+            reason: e.to_string(),
+        })
+    }
+}
+
+fn invalid(reason: &str) -> ContractError {
+    ContractError::InvalidArtifact {
+        path: None,
+        reason: reason.into(),
+    }
+}
+
+fn parse_abi(value: Option<&Value>) -> Result<ethabi::Contract, ContractError> {
+    let value = value.ok_or_else(|| invalid("missing `abi`"))?;
+    serde_json::from_value(value.clone()).map_err(|e| ContractError::InvalidArtifact {
+        path: None,
+        reason: format!("invalid ABI: {e}"),
+    })
+}
+
+/// Parses bytecode given as `{ "object": "...", "linkReferences": { ... } }`.
+fn parse_bytecode_object(value: Option<&Value>) -> Result<Bytecode, ContractError> {
+    match value {
+        None | Some(Value::Null) => Ok(Bytecode::default()),
+        Some(value) => parse_bytecode_str(value.get("object"), value.get("linkReferences")),
+    }
+}
+
+/// Parses bytecode given as a hex string, with link references given separately.
+fn parse_bytecode_str(
+    object: Option<&Value>,
+    link_references: Option<&Value>,
+) -> Result<Bytecode, ContractError> {
+    let object = match object {
+        None | Some(Value::Null) => "",
+        Some(Value::String(s)) => s.strip_prefix("0x").unwrap_or(s),
+        Some(_) => return Err(invalid("bytecode must be a hex string")),
+    };
+    let link_references: LinkReferences = match link_references {
+        None | Some(Value::Null) => BTreeMap::new(),
+        Some(value) => {
+            serde_json::from_value(value.clone()).map_err(|e| ContractError::InvalidArtifact {
+                path: None,
+                reason: format!("invalid link references: {e}"),
+            })?
+        }
+    };
+    Ok(Bytecode {
+        object: object.into(),
+        link_references,
+    })
+}
+
+fn parse_method_identifiers(
+    value: Option<&Value>,
+    abi: &ethabi::Contract,
+) -> Result<BTreeMap<String, String>, ContractError> {
+    match value {
+        Some(value) if !value.is_null() => {
+            serde_json::from_value(value.clone()).map_err(|e| ContractError::InvalidArtifact {
+                path: None,
+                reason: format!("invalid method identifiers: {e}"),
+            })
+        }
+        _ => Ok(abi
+            .functions()
+            .map(|f| (function_signature(f), hex::encode(f.short_signature())))
+            .collect()),
+    }
+}
+
+/// Placeholder used by solc >= 0.5: `__$` + 34 hex chars of the keccak of the fully
+/// qualified library name + `$__`.
+fn hashed_placeholder(file: &str, library: &str) -> String {
+    let hash = aurora_engine_sdk::keccak(format!("{file}:{library}").as_bytes());
+    format!("__${}$__", &hex::encode(hash.as_bytes())[..34])
+}
+
+/// Placeholder used by solc < 0.5 (and Truffle): `__` + the (truncated) library name,
+/// padded with underscores.
+fn legacy_placeholder(library: &str) -> String {
+    let mut placeholder = format!("__{library}");
+    placeholder.truncate(PLACEHOLDER_LEN);
+    format!("{placeholder:_<width$}", width = PLACEHOLDER_LEN)
+}
+
+/// Finds library placeholders in the hex-encoded bytecode.
+fn scan_placeholders(object: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = object;
+    while let Some(index) = rest.find("__") {
+        let end = (index + PLACEHOLDER_LEN).min(rest.len());
+        result.push(rest[index..end].trim_matches('_').to_string());
+        rest = &rest[end..];
+    }
+    result
+}
+
+#[test]
+fn test_load_and_link_foundry_artifact() {
+    let library = Address::decode("00000000000000000000000000000000000000aa").unwrap();
+    let placeholder = hashed_placeholder("src/Codec.sol", "Codec");
+    let code = format!("6080{placeholder}00");
+    let value = serde_json::json!({
+        "abi": [],
+        "bytecode": {
+            "object": format!("0x{code}"),
+            "linkReferences": {"src/Codec.sol": {"Codec": [{"start": 2, "length": 20}]}},
+        },
+        "deployedBytecode": {"object": format!("0x{code}"), "linkReferences": {}},
+    });
+    let mut artifact = SolidityArtifact::from_json(&value, None).unwrap();
+    assert_eq!(artifact.format, ArtifactFormat::Foundry);
+    assert_eq!(artifact.unlinked_libraries(), vec!["src/Codec.sol:Codec"]);
+    assert!(matches!(
+        artifact.clone().into_constructor(),
+        Err(ContractError::UnlinkedLibraries { .. })
+    ));
+    assert!(matches!(
+        artifact.link_library("Utils", library),
+        Err(ContractError::UnknownLibrary { .. })
+    ));
+
+    artifact
+        .link_library("src/Codec.sol:Codec", library)
+        .unwrap();
+    assert!(artifact.unlinked_libraries().is_empty());
+    // The deployed bytecode has no link references, so it was linked via the placeholder.
+    assert!(artifact.deployed_bytecode.is_linked());
+    let constructor = artifact.into_constructor().unwrap();
+    assert_eq!(
+        constructor.code,
+        [&[0x60, 0x80], library.as_bytes(), &[0x00]].concat()
+    );
+}
+
+#[test]
+fn test_detect_artifact_formats() {
+    let abi = serde_json::json!([{
+        "type": "function", "name": "get", "inputs": [], "outputs": [], "stateMutability": "view"
+    }]);
+    let hardhat = serde_json::json!({
+        "_format": "hh-sol-artifact-1", "abi": abi, "bytecode": "0x00",
+        "deployedBytecode": "0x00", "linkReferences": {}, "deployedLinkReferences": {},
+    });
+    let truffle = serde_json::json!({
+        "contractName": "Get", "abi": abi, "bytecode": "0x00", "deployedBytecode": "0x00",
+        "schemaVersion": "3.4.3",
+    });
+    let standard_json = serde_json::json!({
+        "contracts": {"Get.sol": {"Get": {"abi": abi, "evm": {
+            "bytecode": {"object": "00", "linkReferences": {}},
+            "deployedBytecode": {"object": "00", "linkReferences": {}},
+            "methodIdentifiers": {"get()": "6d4ce63c"},
+        }}}},
+    });
+
+    let artifact = SolidityArtifact::from_json(&hardhat, None).unwrap();
+    assert_eq!(artifact.format, ArtifactFormat::Hardhat);
+    assert_eq!(artifact.selector("get()"), Some([0x6d, 0x4c, 0xe6, 0x3c]));
+    let artifact = SolidityArtifact::from_json(&truffle, None).unwrap();
+    assert_eq!(artifact.format, ArtifactFormat::Truffle);
+    assert_eq!(artifact.contract_name.as_deref(), Some("Get"));
+    let artifact = SolidityArtifact::from_json(&standard_json, Some("Get")).unwrap();
+    assert_eq!(artifact.format, ArtifactFormat::SolcStandardJson);
+    assert_eq!(artifact.selector("get()"), Some([0x6d, 0x4c, 0xe6, 0x3c]));
+    assert!(matches!(
+        SolidityArtifact::from_json(&standard_json, Some("Other")),
+        Err(ContractError::ContractNotFound { .. })
+    ));
+}
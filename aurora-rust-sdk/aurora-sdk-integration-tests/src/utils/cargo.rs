@@ -1,45 +1,223 @@
 use crate::utils::process;
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 const NO_SUCH_COMMAND: &str = "no such command";
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+const BUILD_CACHE_DIR: &str = "aurora-sdk-build-cache";
+/// Strips the symbols from the Wasm.
+const STRIP_FLAGS: [&str; 2] = ["-C", "link-arg=-s"];
+
+/// Distinguishes the temporary files of concurrent builds in the same process.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Builds a Wasm artifcact for the Rust contract defined in the given directory.
 /// This function uses the [cargo-near](https://github.com/near/cargo-near) too and
 /// installs this extension if it is not already present. `cargo-near` only works with
 /// contracts that use NEAR SDK version 4.1 or later.
+/// If `cargo-near` cannot be installed (e.g. there is no network access), the contract is
+/// built with `build_contract_with_options` instead.
 pub async fn build_contract<P: AsRef<Path>>(contract_dir: P) -> anyhow::Result<Vec<u8>> {
-    let contract_path = contract_dir.as_ref();
-    check_cargo_near(contract_path).await?;
+    let contract_path = contract_dir.as_ref().canonicalize()?;
+    if check_cargo_near(&contract_path).await.is_err() {
+        return build_contract_with_options(&contract_path, &BuildOptions::default()).await;
+    }
+    let metadata = read_metadata(&contract_path, None).await?;
+    cargo_near_build(&contract_path, &metadata, "--no-abi").await
+}
 
-    let output = tokio::process::Command::new("cargo")
-        .current_dir(contract_path)
-        .args(["near", "build", "--no-abi"])
-        .output()
-        .await?;
-    process::require_success(&output)?;
-
-    let output_text = String::from_utf8_lossy(&output.stderr);
-    let output_lines = output_text.split('\n').map(|line| line.trim());
-    let mut binary_path = None;
-    for line in output_lines {
-        if line.contains("Binary:") {
-            binary_path = Some(line.split_once(": ").unwrap().1);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildOptions {
+    /// Post-process the Wasm with `wasm-opt -Oz`, if `wasm-opt` is installed.
+    pub wasm_opt: bool,
+    /// Embed the contract ABI into the Wasm. This requires `cargo-near` to be installed.
+    pub embed_abi: bool,
+    /// Reuse the previous build output if none of the contract sources changed.
+    pub use_cache: bool,
+    /// Build in (and cache to) this cargo target directory instead of the one configured for
+    /// the contract.
+    pub target_dir: Option<PathBuf>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            wasm_opt: true,
+            embed_abi: false,
+            use_cache: true,
+            target_dir: None,
         }
     }
+}
 
-    if binary_path.is_none() {
-        return Err(anyhow::Error::msg("Failed to parse cargo-near output"));
+/// Builds a Wasm artifact for the Rust contract defined in the given directory using
+/// `cargo build --target wasm32-unknown-unknown --release`, so no cargo extensions (and
+/// no network access for installing them) are needed. The output is cached in the cargo
+/// target directory, keyed by a hash of the sources of the contract and its (transitive)
+/// path dependencies, the `Cargo.lock` of the contract and of its workspace and the rustflags.
+pub async fn build_contract_with_options<P: AsRef<Path>>(
+    contract_dir: P,
+    options: &BuildOptions,
+) -> anyhow::Result<Vec<u8>> {
+    let contract_path = contract_dir.as_ref().canonicalize()?;
+    let target_dir = options.target_dir.as_deref();
+    let metadata = read_metadata(&contract_path, target_dir).await?;
+    let (rustflags_var, rustflags) = rustflags();
+    let cache_dir = metadata.target_directory.join(BUILD_CACHE_DIR);
+    let cache_path = cache_dir.join(format!(
+        "{}-{}.wasm",
+        metadata.wasm_name,
+        hash_sources(
+            &metadata.source_dirs,
+            &metadata.workspace_files,
+            options,
+            &rustflags
+        )?
+    ));
+    if options.use_cache {
+        if let Ok(bytes) = tokio::fs::read(&cache_path).await {
+            return Ok(bytes);
+        }
     }
 
-    // Unwrap is safe by check above
-    let bytes = tokio::fs::read(binary_path.unwrap()).await.map_err(|e| {
-        let x: anyhow::Error = e.into();
-        x.context(format!("Trying to read file: {binary_path:?}"))
-    })?;
+    let bytes = if options.embed_abi {
+        cargo_near_build(&contract_path, &metadata, "--embed-abi").await?
+    } else {
+        check_wasm_target(&contract_path).await?;
+        process::run(
+            cargo_command(&contract_path, target_dir)
+                .env(rustflags_var, &rustflags)
+                .args(["build", "--target", WASM_TARGET, "--release"]),
+        )
+        .await?;
+        let binary_path = metadata
+            .target_directory
+            .join(WASM_TARGET)
+            .join("release")
+            .join(format!("{}.wasm", metadata.wasm_name));
+        tokio::fs::read(&binary_path).await.map_err(|e| {
+            anyhow::Error::new(e).context(format!("Trying to read file: {binary_path:?}"))
+        })?
+    };
+
+    // The output is written (and optimized) in a temporary file which is then renamed, so
+    // that concurrent builds never read a partially written cache file.
+    tokio::fs::create_dir_all(&cache_dir).await?;
+    let temp_path = cache_dir.join(format!(
+        "{}.{}-{}.tmp",
+        metadata.wasm_name,
+        std::process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = write_cache_file(&temp_path, &cache_path, bytes, options.wasm_opt).await;
+    if result.is_err() {
+        // The temporary file may not exist, depending on where the error happened.
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    result
+}
 
+async fn write_cache_file(
+    temp_path: &Path,
+    cache_path: &Path,
+    bytes: Vec<u8>,
+    wasm_opt: bool,
+) -> anyhow::Result<Vec<u8>> {
+    tokio::fs::write(temp_path, &bytes).await?;
+    let bytes = if wasm_opt && has_wasm_opt().await {
+        process::run(
+            tokio::process::Command::new("wasm-opt")
+                .args(["-Oz", "-o"])
+                .arg(temp_path)
+                .arg(temp_path),
+        )
+        .await?;
+        tokio::fs::read(temp_path).await?
+    } else {
+        bytes
+    };
+    tokio::fs::rename(temp_path, cache_path).await?;
     Ok(bytes)
 }
 
+/// The environment variable and value which add `STRIP_FLAGS` to the rustflags set by the
+/// user. Cargo prefers `CARGO_ENCODED_RUSTFLAGS` over `RUSTFLAGS`, and either one replaces
+/// the `build.rustflags` of the cargo configuration.
+fn rustflags() -> (&'static str, String) {
+    if let Some(flags) = std::env::var_os("CARGO_ENCODED_RUSTFLAGS") {
+        let flags = flags.to_string_lossy();
+        let encoded = std::iter::once(flags.as_ref())
+            .filter(|flags| !flags.is_empty())
+            .chain(STRIP_FLAGS)
+            .collect::<Vec<_>>()
+            .join("\x1f");
+        return ("CARGO_ENCODED_RUSTFLAGS", encoded);
+    }
+    let flags = std::env::var("RUSTFLAGS").unwrap_or_default();
+    let flags = std::iter::once(flags.trim())
+        .filter(|flags| !flags.is_empty())
+        .chain(STRIP_FLAGS)
+        .collect::<Vec<_>>()
+        .join(" ");
+    ("RUSTFLAGS", flags)
+}
+
+/// Builds the contract with `cargo near build`. The Wasm is copied to an output directory in
+/// the cargo target directory (`near/{wasm_name}`) which is passed explicitly, so that its
+/// path does not depend on the layout `cargo-near` uses by default.
+async fn cargo_near_build(
+    contract_path: &Path,
+    metadata: &ContractMetadata,
+    abi_flag: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let out_dir = metadata
+        .target_directory
+        .join("near")
+        .join(&metadata.wasm_name);
+    process::run(
+        cargo_command(contract_path, Some(&metadata.target_directory))
+            .args(["near", "build", abi_flag, "--out-dir"])
+            .arg(&out_dir),
+    )
+    .await?;
+
+    let binary_path = out_dir.join(format!("{}.wasm", metadata.wasm_name));
+    tokio::fs::read(&binary_path)
+        .await
+        .map_err(|e| anyhow::Error::new(e).context(format!("Trying to read file: {binary_path:?}")))
+}
+
+/// A `cargo` command run in `dir`, using `target_dir` as the cargo target directory if given.
+fn cargo_command(dir: &Path, target_dir: Option<&Path>) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new("cargo");
+    command.current_dir(dir);
+    if let Some(target_dir) = target_dir {
+        command.env("CARGO_TARGET_DIR", target_dir);
+    }
+    command
+}
+
+/// Fails with a clear error if the standard library for `WASM_TARGET` is not installed for
+/// the toolchain used in `dir`.
+async fn check_wasm_target(dir: &Path) -> anyhow::Result<()> {
+    let output = process::run(
+        tokio::process::Command::new("rustc")
+            .current_dir(dir)
+            .args(["--print", "target-libdir", "--target", WASM_TARGET]),
+    )
+    .await?;
+    let libdir = String::from_utf8_lossy(&output.stdout);
+    if !Path::new(libdir.trim()).is_dir() {
+        return Err(anyhow::Error::msg(format!(
+            "The {WASM_TARGET} target is not installed, add it with \
+             `rustup target add {WASM_TARGET}`"
+        )));
+    }
+    Ok(())
+}
+
 async fn check_cargo_near(contract_path: &Path) -> anyhow::Result<()> {
     let output = tokio::process::Command::new("cargo")
         .current_dir(contract_path)
@@ -51,12 +229,12 @@ async fn check_cargo_near(contract_path: &Path) -> anyhow::Result<()> {
         || contains_error_msg(&output.stderr)
         || contains_error_msg(&output.stdout)
     {
-        let output = tokio::process::Command::new("cargo")
-            .current_dir(contract_path)
-            .args(["install", "cargo-near"])
-            .output()
-            .await?;
-        return process::require_success(&output);
+        process::run(
+            tokio::process::Command::new("cargo")
+                .current_dir(contract_path)
+                .args(["install", "cargo-near"]),
+        )
+        .await?;
     }
 
     Ok(())
@@ -65,3 +243,334 @@ async fn check_cargo_near(contract_path: &Path) -> anyhow::Result<()> {
 fn contains_error_msg(bytes: &[u8]) -> bool {
     String::from_utf8_lossy(bytes).contains(NO_SUCH_COMMAND)
 }
+
+async fn has_wasm_opt() -> bool {
+    tokio::process::Command::new("wasm-opt")
+        .arg("--version")
+        .output()
+        .await
+        .map_or(false, |output| output.status.success())
+}
+
+struct ContractMetadata {
+    target_directory: PathBuf,
+    /// File name (without extension) of the Wasm produced for the contract.
+    wasm_name: String,
+    /// The directory of the contract and of all its (transitive) path dependencies.
+    source_dirs: Vec<PathBuf>,
+    /// The manifest and `Cargo.lock` of the workspace containing the contract, and the
+    /// `Cargo.lock` of the contract itself (which cargo uses if the contract is built as its
+    /// own workspace, e.g. with `[workspace]` in its manifest).
+    workspace_files: Vec<PathBuf>,
+}
+
+/// Uses `cargo metadata` to find the cargo target directory, the library name and the path
+/// dependencies of the contract. `--no-deps` avoids resolving (and downloading) registry
+/// dependencies.
+async fn read_metadata(
+    contract_path: &Path,
+    target_dir: Option<&Path>,
+) -> anyhow::Result<ContractMetadata> {
+    let metadata = cargo_metadata(contract_path, target_dir).await?;
+    let parse_err = || anyhow::Error::msg("Failed to parse cargo metadata output");
+
+    let target_directory = metadata["target_directory"]
+        .as_str()
+        .ok_or_else(parse_err)?
+        .into();
+    let workspace_root = Path::new(metadata["workspace_root"].as_str().ok_or_else(parse_err)?);
+    let package = find_package(&metadata, contract_path)?;
+    let wasm_name = package["targets"]
+        .as_array()
+        .ok_or_else(parse_err)?
+        .iter()
+        .find(|t| {
+            t["kind"]
+                .as_array()
+                .map_or(false, |kinds| kinds.iter().any(|k| k == "cdylib"))
+        })
+        .and_then(|t| t["name"].as_str())
+        .ok_or_else(|| anyhow::Error::msg("Contract package has no cdylib target"))?
+        .replace('-', "_");
+
+    // `--no-deps` only lists the workspace members, so the dependencies of path dependencies
+    // outside of the workspace are read with `cargo metadata` in their own directories.
+    let mut source_dirs = vec![contract_path.to_path_buf()];
+    let mut pending = path_dependencies(package)?;
+    while let Some(dir) = pending.pop() {
+        if source_dirs.contains(&dir) {
+            continue;
+        }
+        let dependencies = match find_package(&metadata, &dir) {
+            Ok(package) => path_dependencies(package)?,
+            Err(_) => {
+                let dependency_metadata = cargo_metadata(&dir, target_dir).await?;
+                path_dependencies(find_package(&dependency_metadata, &dir)?)?
+            }
+        };
+        pending.extend(dependencies);
+        source_dirs.push(dir);
+    }
+
+    Ok(ContractMetadata {
+        target_directory,
+        wasm_name,
+        source_dirs,
+        workspace_files: vec![
+            workspace_root.join("Cargo.toml"),
+            workspace_root.join("Cargo.lock"),
+            contract_path.join("Cargo.lock"),
+        ],
+    })
+}
+
+async fn cargo_metadata(
+    dir: &Path,
+    target_dir: Option<&Path>,
+) -> anyhow::Result<serde_json::Value> {
+    let output = process::run(cargo_command(dir, target_dir).args([
+        "metadata",
+        "--no-deps",
+        "--format-version",
+        "1",
+    ]))
+    .await?;
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// The package of `cargo metadata` output whose manifest is in `dir`.
+fn find_package<'a>(
+    metadata: &'a serde_json::Value,
+    dir: &Path,
+) -> anyhow::Result<&'a serde_json::Value> {
+    let manifest_path = dir.join("Cargo.toml");
+    metadata["packages"]
+        .as_array()
+        .ok_or_else(|| anyhow::Error::msg("Failed to parse cargo metadata output"))?
+        .iter()
+        .find(|p| p["manifest_path"].as_str().map(Path::new) == Some(manifest_path.as_path()))
+        .ok_or_else(|| {
+            anyhow::Error::msg(format!("No package found for {}", manifest_path.display()))
+        })
+}
+
+/// The directories of the path dependencies of the package, except dev-dependencies (which
+/// are not part of the contract).
+fn path_dependencies(package: &serde_json::Value) -> anyhow::Result<Vec<PathBuf>> {
+    let dependencies = package["dependencies"]
+        .as_array()
+        .ok_or_else(|| anyhow::Error::msg("Failed to parse cargo metadata output"))?;
+    Ok(dependencies
+        .iter()
+        .filter(|d| d["kind"] != "dev")
+        .filter_map(|d| d["path"].as_str())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// Hashes the build options and rustflags together with the given files (if they exist)
+/// and the manifests and Rust sources found in the given directories (skipping `target`
+/// directories).
+fn hash_sources(
+    dirs: &[PathBuf],
+    files: &[PathBuf],
+    options: &BuildOptions,
+    rustflags: &str,
+) -> anyhow::Result<String> {
+    let mut files: Vec<PathBuf> = files.iter().filter(|f| f.is_file()).cloned().collect();
+    for dir in dirs {
+        collect_source_files(dir, &mut files)?;
+    }
+    files.sort();
+    files.dedup();
+
+    let mut data = format!("{options:?} {rustflags:?}").into_bytes();
+    for file in files {
+        data.extend_from_slice(file.to_string_lossy().as_bytes());
+        data.extend_from_slice(&std::fs::read(&file)?);
+    }
+    let hash = aurora_engine_sdk::keccak(&data);
+    Ok(hex::encode(&hash.as_bytes()[..16]))
+}
+
+fn collect_source_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            let is_hidden = path
+                .file_name()
+                .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+            if !is_hidden && !path.ends_with("target") {
+                collect_source_files(&path, files)?;
+            }
+        } else {
+            let is_source = path.extension().map_or(false, |ext| ext == "rs")
+                || path.file_name().map_or(false, |name| {
+                    name == "Cargo.toml"
+                        || name == "Cargo.lock"
+                        || name.to_string_lossy().starts_with("rust-toolchain")
+                });
+            if is_source {
+                files.push(path);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+async fn write_test_crate(dir: &Path, name: &str, manifest_extra: &str, source: &str) {
+    tokio::fs::create_dir_all(dir.join("src")).await.unwrap();
+    let manifest = format!(
+        "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n{manifest_extra}"
+    );
+    tokio::fs::write(dir.join("Cargo.toml"), manifest)
+        .await
+        .unwrap();
+    tokio::fs::write(dir.join("src").join("lib.rs"), source)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_hash_sources_covers_dependencies() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    // The contract is a member of a workspace and depends on `dep_a` outside of it, which
+    // depends on `dep_b`.
+    tokio::fs::create_dir_all(dir.join("workspace"))
+        .await
+        .unwrap();
+    tokio::fs::write(
+        dir.join("workspace").join("Cargo.toml"),
+        "[workspace]\nmembers = [\"contract\"]\n",
+    )
+    .await
+    .unwrap();
+    let contract_path = dir.join("workspace").join("contract");
+    write_test_crate(
+        &contract_path,
+        "contract",
+        "[lib]\ncrate-type = [\"cdylib\"]\n[dependencies]\ndep_a = { path = \"../../dep_a\" }\n",
+        "",
+    )
+    .await;
+    write_test_crate(
+        &dir.join("dep_a"),
+        "dep_a",
+        "[dependencies]\ndep_b = { path = \"../dep_b\" }\n",
+        "",
+    )
+    .await;
+    write_test_crate(&dir.join("dep_b"), "dep_b", "", "").await;
+
+    let contract_path = contract_path.canonicalize().unwrap();
+    let metadata = read_metadata(&contract_path, None).await.unwrap();
+    assert_eq!(metadata.wasm_name, "contract");
+    assert_eq!(metadata.source_dirs.len(), 3);
+    let options = BuildOptions::default();
+    let hash = || {
+        hash_sources(
+            &metadata.source_dirs,
+            &metadata.workspace_files,
+            &options,
+            "-C link-arg=-s",
+        )
+        .unwrap()
+    };
+    let initial_hash = hash();
+
+    tokio::fs::write(
+        dir.join("dep_b").join("src").join("lib.rs"),
+        "pub fn f() {}",
+    )
+    .await
+    .unwrap();
+    let dep_b_hash = hash();
+    assert_ne!(dep_b_hash, initial_hash);
+
+    tokio::fs::write(dir.join("workspace").join("Cargo.lock"), "version = 3\n")
+        .await
+        .unwrap();
+    let workspace_lock_hash = hash();
+    assert_ne!(workspace_lock_hash, dep_b_hash);
+
+    tokio::fs::write(contract_path.join("Cargo.lock"), "version = 3\n")
+        .await
+        .unwrap();
+    let lock_hash = hash();
+    assert_ne!(lock_hash, workspace_lock_hash);
+
+    let rustflags_hash = hash_sources(
+        &metadata.source_dirs,
+        &metadata.workspace_files,
+        &options,
+        "-C target-cpu=mvp -C link-arg=-s",
+    )
+    .unwrap();
+    assert_ne!(rustflags_hash, lock_hash);
+}
+
+/// Requires the `wasm32-unknown-unknown` target to be installed. The contract is built in a
+/// temporary target directory, so the cache of other builds is not touched.
+#[tokio::test]
+async fn test_build_contract_with_options_cache() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path().join("contract");
+    check_wasm_target(temp_dir.path()).await.unwrap();
+    write_test_crate(
+        &dir,
+        "cache_test",
+        "[lib]\ncrate-type = [\"cdylib\"]\n[workspace]\n",
+        "#[no_mangle]\npub extern \"C\" fn hello() {}\n",
+    )
+    .await;
+    let target_dir = temp_dir.path().join("target");
+    let options = BuildOptions {
+        wasm_opt: false,
+        embed_abi: false,
+        use_cache: true,
+        target_dir: Some(target_dir.clone()),
+    };
+    let cache_dir = target_dir.join(BUILD_CACHE_DIR);
+    let cache_files = || {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        files
+    };
+
+    let bytes = build_contract_with_options(&dir, &options).await.unwrap();
+    assert!(bytes.starts_with(b"\0asm"));
+    // Only the renamed cache file is left, no temporary file.
+    let files = cache_files();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].extension().unwrap(), "wasm");
+
+    // A cached build returns the content of the cache file.
+    std::fs::write(&files[0], b"cached").unwrap();
+    let cached = build_contract_with_options(&dir, &options).await.unwrap();
+    assert_eq!(cached, b"cached");
+
+    // Without the cache, the contract is rebuilt and the cache file replaced.
+    let no_cache = BuildOptions {
+        use_cache: false,
+        ..options.clone()
+    };
+    let rebuilt = build_contract_with_options(&dir, &no_cache).await.unwrap();
+    assert_eq!(rebuilt, bytes);
+    assert_eq!(std::fs::read(&files[0]).unwrap(), bytes);
+
+    // Changing the sources changes the cache key.
+    tokio::fs::write(
+        dir.join("src").join("lib.rs"),
+        "#[no_mangle]\npub extern \"C\" fn goodbye() {}\n",
+    )
+    .await
+    .unwrap();
+    let changed = build_contract_with_options(&dir, &options).await.unwrap();
+    assert_ne!(changed, bytes);
+    assert_eq!(cache_files().len(), 2);
+}
@@ -1,5 +1,9 @@
+use crate::{aurora_engine::AbiContract, utils::artifact::SolidityArtifact};
 use aurora_engine_types::types::Address;
-use std::path::Path;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ContractConstructor {
@@ -8,17 +12,26 @@ pub struct ContractConstructor {
 }
 
 impl ContractConstructor {
+    /// Loads a Solidity artifact in the "extended JSON" format (i.e. a JSON object with
+    /// `abi` and hex-encoded `bytecode` fields, as produced by Hardhat).
+    /// Panics if the file cannot be loaded; see `try_from_extended_json` for a fallible version.
     pub fn from_extended_json<P>(contract_path: P) -> Self
     where
         P: AsRef<Path>,
     {
-        let reader = std::fs::File::open(contract_path).unwrap();
-        let contract: ExtendedJsonSolidityArtifact = serde_json::from_reader(reader).unwrap();
+        Self::try_from_extended_json(contract_path).unwrap()
+    }
 
-        Self {
-            abi: contract.abi,
-            code: hex::decode(&contract.bytecode[2..]).unwrap(),
-        }
+    /// Fallible version of `from_extended_json`. Any artifact format supported by
+    /// `SolidityArtifact` is accepted, as long as it does not need library linking.
+    pub fn try_from_extended_json<P>(contract_path: P) -> Result<Self, ContractError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = contract_path.as_ref();
+        SolidityArtifact::load(path, None)?
+            .into_constructor()
+            .map_err(|e| e.with_path(path))
     }
 
     pub fn deployed_at(self, address: Address) -> DeployedContract {
@@ -48,11 +61,28 @@ impl ContractConstructor {
     /// This function does not interact with any EVM itself, it only produces the bytes
     /// needed to pass to an EVM.
     pub fn create_deploy_bytes_with_args(&self, args: &[ethabi::Token]) -> Vec<u8> {
-        self.abi
-            .constructor()
-            .unwrap()
+        self.try_create_deploy_bytes_with_args(args).unwrap()
+    }
+
+    /// Fallible version of `create_deploy_bytes_with_args`. Returns an error if the
+    /// arguments do not match the constructor parameters.
+    pub fn try_create_deploy_bytes_with_args(
+        &self,
+        args: &[ethabi::Token],
+    ) -> Result<Vec<u8>, ContractError> {
+        let constructor = match self.abi.constructor() {
+            Some(constructor) => constructor,
+            // A contract without a constructor in its ABI is deployed by its code alone.
+            None if args.is_empty() => return Ok(self.code.clone()),
+            None => return Err(ContractError::MissingConstructor),
+        };
+        check_args(CONSTRUCTOR, &constructor.inputs, args)?;
+        constructor
             .encode_input(self.code.clone(), args)
-            .unwrap()
+            .map_err(|e| ContractError::Encoding {
+                method: CONSTRUCTOR.into(),
+                reason: e.to_string(),
+            })
     }
 }
 
@@ -62,6 +92,16 @@ pub struct DeployedContract {
     pub address: Address,
 }
 
+impl AbiContract for DeployedContract {
+    fn abi(&self) -> &ethabi::Contract {
+        &self.abi
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+}
+
 impl DeployedContract {
     /// Creates the bytes that are used as the input to an EVM transaction for calling the
     /// given function of the Solidity contract. The function must not take any arguments.
@@ -80,16 +120,352 @@ impl DeployedContract {
         method_name: &str,
         args: &[ethabi::Token],
     ) -> Vec<u8> {
-        self.abi
-            .function(method_name)
+        self.try_create_call_method_bytes_with_args(method_name, args)
             .unwrap()
-            .encode_input(args)
+    }
+
+    /// Fallible version of `create_call_method_bytes_with_args`. If the function is
+    /// overloaded, the overload whose parameters match the given arguments is used.
+    pub fn try_create_call_method_bytes_with_args(
+        &self,
+        method_name: &str,
+        args: &[ethabi::Token],
+    ) -> Result<Vec<u8>, ContractError> {
+        let overloads =
+            self.abi
+                .functions_by_name(method_name)
+                .map_err(|_| ContractError::UnknownMethod {
+                    method: method_name.into(),
+                })?;
+        let function = match overloads.as_slice() {
+            [function] => function,
+            _ => {
+                let mut matching = overloads.iter().filter(|f| {
+                    f.inputs.len() == args.len()
+                        && args
+                            .iter()
+                            .zip(&f.inputs)
+                            .all(|(arg, param)| arg.type_check(&param.kind))
+                });
+                match (matching.next(), matching.next()) {
+                    (Some(function), None) => function,
+                    (None, _) => {
+                        return Err(ContractError::NoMatchingOverload {
+                            method: method_name.into(),
+                            candidates: overloads.iter().map(function_signature).collect(),
+                        })
+                    }
+                    (Some(_), Some(_)) => {
+                        return Err(ContractError::AmbiguousOverload {
+                            method: method_name.into(),
+                            candidates: overloads.iter().map(function_signature).collect(),
+                        })
+                    }
+                }
+            }
+        };
+        encode_call(function, args)
+    }
+
+    /// Creates the bytes that are used as the input to an EVM transaction for calling the
+    /// function with the given signature (e.g. `transfer(address,uint256)`). This is useful
+    /// to select a specific overload of a function. This function does not interact with
+    /// any EVM itself, it only produces the bytes needed to pass to an EVM.
+    pub fn create_call_method_bytes_by_signature(
+        &self,
+        signature: &str,
+        args: &[ethabi::Token],
+    ) -> Vec<u8> {
+        self.try_create_call_method_bytes_by_signature(signature, args)
             .unwrap()
     }
+
+    /// Fallible version of `create_call_method_bytes_by_signature`.
+    pub fn try_create_call_method_bytes_by_signature(
+        &self,
+        signature: &str,
+        args: &[ethabi::Token],
+    ) -> Result<Vec<u8>, ContractError> {
+        let expected = signature.replace(' ', "");
+        let function = self
+            .abi
+            .functions()
+            .find(|f| function_signature(f) == expected)
+            .ok_or_else(|| ContractError::UnknownMethod {
+                method: signature.into(),
+            })?;
+        encode_call(function, args)
+    }
+}
+
+/// Errors that can occur while loading a Solidity artifact or encoding the input for
+/// one of its functions.
+#[derive(Debug)]
+pub enum ContractError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    InvalidArtifact {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+    InvalidBytecode {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+    /// A `solc` standard-JSON output does not contain exactly one contract matching `name`.
+    ContractNotFound {
+        path: Option<PathBuf>,
+        name: Option<String>,
+        available: Vec<String>,
+    },
+    UnlinkedLibraries {
+        libraries: Vec<String>,
+    },
+    UnknownLibrary {
+        library: String,
+        unlinked: Vec<String>,
+    },
+    MissingConstructor,
+    UnknownMethod {
+        method: String,
+    },
+    NoMatchingOverload {
+        method: String,
+        candidates: Vec<String>,
+    },
+    AmbiguousOverload {
+        method: String,
+        candidates: Vec<String>,
+    },
+    WrongArgumentCount {
+        method: String,
+        expected: usize,
+        actual: usize,
+    },
+    ArgumentTypeMismatch {
+        method: String,
+        index: usize,
+        param_name: String,
+        expected: ethabi::ParamType,
+        actual: ethabi::Token,
+    },
+    Encoding {
+        method: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ContractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Failed to read {}: {source}", path.display()),
+            Self::Json { path, source } => {
+                write!(f, "Failed to parse {}: {source}", path.display())
+            }
+            Self::InvalidArtifact { path, reason } => {
+                write!(f, "Invalid Solidity artifact{}: {reason}", display_path(path))
+            }
+            Self::InvalidBytecode { path, reason } => {
+                write!(f, "Invalid bytecode{}: {reason}", display_path(path))
+            }
+            Self::ContractNotFound {
+                path,
+                name,
+                available,
+            } => write!(
+                f,
+                "Expected exactly one contract matching {}{}; available: {}",
+                name.as_deref().unwrap_or("*"),
+                display_path(path),
+                available.join(", ")
+            ),
+            Self::UnlinkedLibraries { libraries } => write!(
+                f,
+                "Bytecode has unlinked libraries: {}",
+                libraries.join(", ")
+            ),
+            Self::UnknownLibrary { library, unlinked } => write!(
+                f,
+                "Bytecode does not reference library {library}; unlinked libraries: {}",
+                unlinked.join(", ")
+            ),
+            Self::MissingConstructor => write!(f, "Contract ABI has no constructor"),
+            Self::UnknownMethod { method } => write!(f, "Contract ABI has no method {method}"),
+            Self::NoMatchingOverload { method, candidates } => write!(
+                f,
+                "No overload of {method} matches the given arguments; candidates: {}",
+                candidates.join(", ")
+            ),
+            Self::AmbiguousOverload { method, candidates } => write!(
+                f,
+                "Multiple overloads of {method} match the given arguments, select one by signature; candidates: {}",
+                candidates.join(", ")
+            ),
+            Self::WrongArgumentCount {
+                method,
+                expected,
+                actual,
+            } => write!(f, "{method} takes {expected} arguments, got {actual}"),
+            Self::ArgumentTypeMismatch {
+                method,
+                index,
+                param_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Argument {index} ({param_name}) of {method} must be {expected}, got {actual:?}"
+            ),
+            Self::Encoding { method, reason } => {
+                write!(f, "Failed to encode input for {method}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContractError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Json { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl ContractError {
+    /// Records the path of the artifact the error occurred in, if not already known.
+    pub fn with_path(self, artifact_path: &Path) -> Self {
+        let fill = |path: Option<PathBuf>| path.or_else(|| Some(artifact_path.into()));
+        match self {
+            Self::InvalidArtifact { path, reason } => Self::InvalidArtifact {
+                path: fill(path),
+                reason,
+            },
+            Self::InvalidBytecode { path, reason } => Self::InvalidBytecode {
+                path: fill(path),
+                reason,
+            },
+            Self::ContractNotFound {
+                path,
+                name,
+                available,
+            } => Self::ContractNotFound {
+                path: fill(path),
+                name,
+                available,
+            },
+            other => other,
+        }
+    }
 }
 
-#[derive(serde::Deserialize)]
-struct ExtendedJsonSolidityArtifact {
-    abi: ethabi::Contract,
-    bytecode: String,
+fn display_path(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => format!(" in {}", path.display()),
+        None => String::new(),
+    }
+}
+
+const CONSTRUCTOR: &str = "constructor";
+
+fn encode_call(
+    function: &ethabi::Function,
+    args: &[ethabi::Token],
+) -> Result<Vec<u8>, ContractError> {
+    let method = function_signature(function);
+    check_args(&method, &function.inputs, args)?;
+    function
+        .encode_input(args)
+        .map_err(|e| ContractError::Encoding {
+            method,
+            reason: e.to_string(),
+        })
+}
+
+fn check_args(
+    method: &str,
+    params: &[ethabi::Param],
+    args: &[ethabi::Token],
+) -> Result<(), ContractError> {
+    if params.len() != args.len() {
+        return Err(ContractError::WrongArgumentCount {
+            method: method.into(),
+            expected: params.len(),
+            actual: args.len(),
+        });
+    }
+    match params
+        .iter()
+        .zip(args)
+        .enumerate()
+        .find(|(_, (param, arg))| !arg.type_check(&param.kind))
+    {
+        Some((index, (param, arg))) => Err(ContractError::ArgumentTypeMismatch {
+            method: method.into(),
+            index,
+            param_name: param.name.clone(),
+            expected: param.kind.clone(),
+            actual: arg.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The signature of the function without its outputs, e.g. `transfer(address,uint256)`.
+pub(crate) fn function_signature(function: &ethabi::Function) -> String {
+    let inputs: Vec<String> = function.inputs.iter().map(|p| p.kind.to_string()).collect();
+    format!("{}({})", function.name, inputs.join(","))
+}
+
+#[test]
+fn test_overloaded_method_selection() {
+    let abi: ethabi::Contract = serde_json::from_str(
+        r#"[
+            {"type": "function", "name": "safeTransfer", "stateMutability": "nonpayable", "outputs": [],
+             "inputs": [{"name": "to", "type": "address"}, {"name": "id", "type": "uint256"}]},
+            {"type": "function", "name": "safeTransfer", "stateMutability": "nonpayable", "outputs": [],
+             "inputs": [{"name": "to", "type": "address"}, {"name": "id", "type": "uint256"}, {"name": "data", "type": "bytes"}]}
+        ]"#,
+    )
+    .unwrap();
+    let contract = DeployedContract {
+        abi,
+        address: Address::default(),
+    };
+    let to = ethabi::Token::Address(Address::default().raw());
+    let id = ethabi::Token::Uint(1.into());
+
+    let by_name = contract
+        .try_create_call_method_bytes_with_args("safeTransfer", &[to.clone(), id.clone()])
+        .unwrap();
+    let by_signature = contract
+        .try_create_call_method_bytes_by_signature(
+            "safeTransfer(address, uint256)",
+            &[to.clone(), id.clone()],
+        )
+        .unwrap();
+    assert_eq!(by_name, by_signature);
+
+    let err = contract
+        .try_create_call_method_bytes_with_args("safeTransfer", &[id.clone(), to.clone()])
+        .unwrap_err();
+    assert!(matches!(err, ContractError::NoMatchingOverload { .. }));
+    let err = contract
+        .try_create_call_method_bytes_by_signature("safeTransfer(address,uint256)", &[id, to])
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ArgumentTypeMismatch { index: 0, ref param_name, .. } if param_name == "to"
+    ));
+    let err = contract
+        .try_create_call_method_bytes_with_args("transfer", &[])
+        .unwrap_err();
+    assert!(matches!(err, ContractError::UnknownMethod { .. }));
 }
@@ -1,14 +1,19 @@
 use crate::{
     aurora_engine::AuroraEngine,
-    utils::{ethabi::ContractConstructor, process},
+    utils::{
+        artifact::SolidityArtifact,
+        ethabi::{ContractConstructor, DeployedContract},
+        lock::FileLock,
+        process,
+    },
 };
 use aurora_engine_types::types::Address;
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
 use tokio::{process::Command, sync::Mutex};
-
-/// A lock to prevent multiple tests from compiling the Solidity contracts with different
-/// library addresses at the same time.
-static FORGE_LOCK: Mutex<()> = Mutex::const_new(());
+use workspaces::{network::Sandbox, Worker};
 
 pub async fn deploy_codec_lib<P: AsRef<Path>>(
     aurora_sdk_path: P,
@@ -34,17 +39,18 @@ pub async fn deploy_aurora_sdk_lib<P: AsRef<Path>>(
     codec_lib: Address,
     utils_lib: Address,
 ) -> anyhow::Result<Address> {
-    let aurora_sdk = forge_build(
+    let mut aurora_sdk = forge_build_artifact(
         aurora_sdk_path,
-        &[
-            format!("src/Codec.sol:Codec:0x{}", codec_lib.encode()),
-            format!("src/Utils.sol:Utils:0x{}", utils_lib.encode()),
-        ],
         &["out", "AuroraSdk.sol", "AuroraSdk.json"],
+        &[],
     )
     .await?;
+    aurora_sdk.link_library("src/Codec.sol:Codec", codec_lib)?;
+    aurora_sdk.link_library("src/Utils.sol:Utils", utils_lib)?;
 
-    let address = engine.deploy_evm_contract(aurora_sdk.code).await?;
+    let address = engine
+        .deploy_evm_contract(aurora_sdk.into_constructor()?.code)
+        .await?;
     Ok(address)
 }
 
@@ -62,47 +68,215 @@ pub async fn forge_build_with_args<P: AsRef<Path>>(
     contract_output_path: &[&str],
     extra_args: &[&str],
 ) -> anyhow::Result<ContractConstructor> {
-    let _guard = FORGE_LOCK.lock().await;
-    let contracts_path = root_path.as_ref();
-    let args = std::iter::once("build")
-        .chain(libraries.iter().flat_map(|x| ["--libraries", x]))
-        .chain(extra_args.iter().copied());
-
-    let output = Command::new("forge")
-        .current_dir(contracts_path)
-        .args(args)
-        .output()
-        .await?;
-    process::require_success(&output)?;
+    let args: Vec<&str> = libraries
+        .iter()
+        .flat_map(|x| ["--libraries", x])
+        .chain(extra_args.iter().copied())
+        .collect();
+    let artifact = forge_build_artifact(root_path, contract_output_path, &args).await?;
+    Ok(artifact.into_constructor()?)
+}
+
+/// Builds the project and loads the given artifact without requiring its libraries to be
+/// linked. Libraries can then be linked with `SolidityArtifact::link_library`, which
+/// avoids rebuilding the project for each set of library addresses.
+pub async fn forge_build_artifact<P: AsRef<Path>>(
+    root_path: P,
+    contract_output_path: &[&str],
+    extra_args: &[&str],
+) -> anyhow::Result<SolidityArtifact> {
+    let mut artifacts =
+        forge_build_artifacts(root_path, &[contract_output_path], extra_args).await?;
+    // Unwrap is safe because exactly one artifact was requested.
+    Ok(artifacts.pop().unwrap())
+}
 
-    let s = tokio::fs::read_to_string(
-        contracts_path.join(contract_output_path.iter().collect::<PathBuf>()),
+/// Builds the project once and loads all the given artifacts.
+async fn forge_build_artifacts<P: AsRef<Path>>(
+    root_path: P,
+    contract_output_paths: &[&[&str]],
+    extra_args: &[&str],
+) -> anyhow::Result<Vec<SolidityArtifact>> {
+    let contracts_path = root_path.as_ref();
+    // Prevents multiple tests (possibly in different processes) from running `forge build`
+    // in the same project at the same time, since they share the output directory.
+    let project_hash =
+        aurora_engine_sdk::keccak(contracts_path.canonicalize()?.to_string_lossy().as_bytes());
+    let _guard = FileLock::acquire_named(&format!(
+        "forge-{}",
+        hex::encode(&project_hash.as_bytes()[..8])
+    ))
+    .await?;
+    process::run(
+        Command::new("forge")
+            .current_dir(contracts_path)
+            .arg("build")
+            .args(extra_args),
     )
     .await?;
-    let result: serde_json::Value = serde_json::from_str(&s)?;
 
-    let code_hex =
-        json_lens(&result, &["bytecode", "object"], |x| x.as_str()).ok_or_else(forge_parse_err)?;
-    let code_hex = code_hex.strip_prefix("0x").unwrap_or(code_hex);
-    let code = hex::decode(code_hex)?;
+    // The artifacts must be read while holding the lock, before another build overwrites them.
+    contract_output_paths
+        .iter()
+        .map(|output_path| -> anyhow::Result<SolidityArtifact> {
+            let path = contracts_path.join(output_path.iter().collect::<PathBuf>());
+            Ok(SolidityArtifact::load(path, None)?)
+        })
+        .collect()
+}
 
-    let abi_data = json_lens(&result, &["abi"], Some).ok_or_else(forge_parse_err)?;
-    let abi = serde_json::from_value(abi_data.clone())?;
+/// Addresses of the SDK libraries deployed by `AuroraSdkDeployment::get_or_deploy`, keyed by
+/// the worker's RPC address, the engine account ID and the SDK path.
+static DEPLOYMENTS: Mutex<BTreeMap<(String, String, PathBuf), [Address; 3]>> =
+    Mutex::const_new(BTreeMap::new());
 
-    Ok(ContractConstructor { code, abi })
+/// The Aurora Solidity SDK libraries (`Codec`, `Utils` and `AuroraSdk`), deployed and linked
+/// on an engine. Deploy it once per engine and reuse it for all the contracts which depend
+/// on the SDK, e.g. with `get_or_deploy`.
+#[derive(Clone)]
+pub struct AuroraSdkDeployment {
+    pub engine: AuroraEngine,
+    pub codec_lib: Address,
+    pub utils_lib: Address,
+    pub aurora_sdk_lib: Address,
 }
 
-fn json_lens<'a, T, F>(value: &'a serde_json::Value, keys: &[&str], interp: F) -> Option<T>
-where
-    F: FnOnce(&'a serde_json::Value) -> Option<T>,
-{
-    let mut value = value;
-    for k in keys {
-        value = value.as_object()?.get(*k)?;
+impl AuroraSdkDeployment {
+    /// Builds the SDK found at `aurora_sdk_path` (the root of the `aurora-solidity-sdk`
+    /// Foundry project) and deploys its libraries to the engine.
+    pub async fn deploy<P: AsRef<Path>>(
+        aurora_sdk_path: P,
+        engine: &AuroraEngine,
+    ) -> anyhow::Result<Self> {
+        let mut artifacts = forge_build_artifacts(
+            aurora_sdk_path,
+            &[
+                &["out", "Codec.sol", "Codec.json"],
+                &["out", "Utils.sol", "Utils.json"],
+                &["out", "AuroraSdk.sol", "AuroraSdk.json"],
+            ],
+            &[],
+        )
+        .await?
+        .into_iter();
+        // Unwraps are safe because exactly three artifacts were requested.
+        let (codec, utils, mut aurora_sdk) = (
+            artifacts.next().unwrap(),
+            artifacts.next().unwrap(),
+            artifacts.next().unwrap(),
+        );
+
+        let codec_lib = engine
+            .deploy_evm_contract(codec.into_constructor()?.code)
+            .await?;
+        let utils_lib = engine
+            .deploy_evm_contract(utils.into_constructor()?.code)
+            .await?;
+        aurora_sdk.link_library("Codec", codec_lib)?;
+        aurora_sdk.link_library("Utils", utils_lib)?;
+        let aurora_sdk_lib = engine
+            .deploy_evm_contract(aurora_sdk.into_constructor()?.code)
+            .await?;
+
+        Ok(Self {
+            engine: engine.clone(),
+            codec_lib,
+            utils_lib,
+            aurora_sdk_lib,
+        })
+    }
+
+    /// Same as `deploy`, but only builds and deploys the SDK the first time it is called for
+    /// a given engine (on the given worker). Later calls return the same libraries.
+    pub async fn get_or_deploy<P: AsRef<Path>>(
+        aurora_sdk_path: P,
+        worker: &Worker<Sandbox>,
+        engine: &AuroraEngine,
+    ) -> anyhow::Result<Self> {
+        let key = (
+            worker.rpc_addr(),
+            engine.inner.id().to_string(),
+            aurora_sdk_path.as_ref().canonicalize()?,
+        );
+        // The lock is held while deploying so that concurrent callers deploy only once.
+        let mut deployments = DEPLOYMENTS.lock().await;
+        if let Some(&[codec_lib, utils_lib, aurora_sdk_lib]) = deployments.get(&key) {
+            // The RPC address of a stopped sandbox can be reused by a new one, in which case
+            // the libraries are not deployed on this engine.
+            if !engine.get_code(aurora_sdk_lib).await?.is_empty() {
+                return Ok(Self {
+                    engine: engine.clone(),
+                    codec_lib,
+                    utils_lib,
+                    aurora_sdk_lib,
+                });
+            }
+        }
+        let deployment = Self::deploy(aurora_sdk_path, engine).await?;
+        deployments.insert(
+            key,
+            [
+                deployment.codec_lib,
+                deployment.utils_lib,
+                deployment.aurora_sdk_lib,
+            ],
+        );
+        Ok(deployment)
     }
-    interp(value)
-}
 
-fn forge_parse_err() -> anyhow::Error {
-    anyhow::Error::msg("Failed to parse Forge output")
+    /// The names and addresses of the deployed libraries.
+    pub fn libraries(&self) -> [(&'static str, Address); 3] {
+        [
+            ("Codec", self.codec_lib),
+            ("Utils", self.utils_lib),
+            ("AuroraSdk", self.aurora_sdk_lib),
+        ]
+    }
+
+    /// Links all the SDK libraries referenced by the artifact.
+    pub fn link(&self, artifact: &mut SolidityArtifact) -> anyhow::Result<()> {
+        let unlinked = artifact.unlinked_libraries();
+        for (name, address) in self.libraries() {
+            let is_referenced = unlinked
+                .iter()
+                .any(|library| library.rsplit(':').next() == Some(name));
+            if is_referenced {
+                artifact.link_library(name, address)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the Foundry project at `contract_dir`, links the contract found at
+    /// `contract_output_path` (e.g. `["out", "TicTacToe.sol", "TicTacToe.json"]`) against
+    /// the SDK libraries and deploys it with the given constructor arguments.
+    pub async fn build_and_deploy_linked<P: AsRef<Path>>(
+        &self,
+        contract_dir: P,
+        contract_output_path: &[&str],
+        constructor_args: &[ethabi::Token],
+    ) -> anyhow::Result<DeployedContract> {
+        self.build_and_deploy_linked_with(
+            self.engine.inner.as_account(),
+            contract_dir,
+            contract_output_path,
+            constructor_args,
+        )
+        .await
+    }
+
+    pub async fn build_and_deploy_linked_with<P: AsRef<Path>>(
+        &self,
+        account: &workspaces::Account,
+        contract_dir: P,
+        contract_output_path: &[&str],
+        constructor_args: &[ethabi::Token],
+    ) -> anyhow::Result<DeployedContract> {
+        let mut artifact = forge_build_artifact(contract_dir, contract_output_path, &[]).await?;
+        self.link(&mut artifact)?;
+        let constructor = artifact.into_constructor()?;
+        let code = constructor.try_create_deploy_bytes_with_args(constructor_args)?;
+        let address = self.engine.deploy_evm_contract_with(account, code).await?;
+        Ok(constructor.deployed_at(address))
+    }
 }
@@ -21,20 +21,101 @@ impl Git {
     }
 
     pub async fn clone(&self, url: &str) -> anyhow::Result<()> {
-        let output = self.git_command().args(["clone", url]).output().await?;
-        process::require_success(&output)?;
+        process::run(self.git_command().args(["clone", url])).await?;
         Ok(())
     }
 
     pub async fn checkout(&self, tag: &str) -> anyhow::Result<()> {
-        let output = self.git_command().args(["checkout", tag]).output().await?;
-        process::require_success(&output)?;
+        process::run(self.git_command().args(["checkout", tag])).await?;
         Ok(())
     }
 
     pub async fn fetch(&self, remote: &str) -> anyhow::Result<()> {
-        let output = self.git_command().args(["fetch", remote]).output().await?;
-        process::require_success(&output)?;
+        process::run(self.git_command().args(["fetch", remote])).await?;
+        Ok(())
+    }
+
+    /// Fetches the given refspecs (or commit hashes) from the remote, e.g.
+    /// `+refs/*:refs/remotes/mirror/*` for all of its refs.
+    pub async fn fetch_refspecs(&self, remote: &str, refspecs: &[&str]) -> anyhow::Result<()> {
+        process::run(self.git_command().arg("fetch").arg(remote).args(refspecs)).await?;
+        Ok(())
+    }
+
+    /// Fetches only the given commit (or ref), without its history.
+    pub async fn fetch_shallow(&self, remote: &str, rev: &str) -> anyhow::Result<()> {
+        process::run(
+            self.git_command()
+                .args(["fetch", "--depth", "1", remote, rev]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn init(&self) -> anyhow::Result<()> {
+        process::run(self.git_command().arg("init")).await?;
+        Ok(())
+    }
+
+    pub async fn remote_add(&self, name: &str, url: &str) -> anyhow::Result<()> {
+        process::run(self.git_command().args(["remote", "add", name, url])).await?;
+        Ok(())
+    }
+
+    /// Resolves the revision to a full commit hash.
+    pub async fn rev_parse(&self, rev: &str) -> anyhow::Result<String> {
+        let output = process::run(self.git_command().args([
+            "rev-parse",
+            "--verify",
+            &format!("{rev}^{{commit}}"),
+        ]))
+        .await?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    pub async fn has_commit(&self, rev: &str) -> bool {
+        self.rev_parse(rev).await.is_ok()
+    }
+
+    /// Checks out the revision and verifies that HEAD is at the expected commit afterwards.
+    /// If `rev` is a full commit hash, HEAD must be exactly that commit.
+    pub async fn checkout_verified(&self, rev: &str) -> anyhow::Result<()> {
+        let expected = self.rev_parse(rev).await?;
+        if is_commit_hash(rev) && !expected.eq_ignore_ascii_case(rev) {
+            return Err(anyhow::Error::msg(format!(
+                "Revision {rev} resolved to unexpected commit {expected}"
+            )));
+        }
+        self.checkout(rev).await?;
+        let head = self.rev_parse("HEAD").await?;
+        if head != expected {
+            return Err(anyhow::Error::msg(format!(
+                "Expected HEAD at {expected} after checking out {rev}, found {head}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the working directory is the root of a usable git repository. A
+    /// directory left behind by an interrupted clone is not.
+    pub async fn is_repo_root(&self) -> bool {
+        self.working_dir.join(".git").exists()
+            && process::run(self.git_command().args(["rev-parse", "--git-dir"]))
+                .await
+                .is_ok()
+    }
+
+    /// Returns `true` if there are modified or untracked (but not ignored) files.
+    pub async fn is_dirty(&self) -> anyhow::Result<bool> {
+        let output = process::run(self.git_command().args(["status", "--porcelain"])).await?;
+        Ok(!output.stdout.is_empty())
+    }
+
+    /// Discards all local modifications and removes untracked files. Ignored files (such as
+    /// build outputs) are kept.
+    pub async fn clean(&self) -> anyhow::Result<()> {
+        process::run(self.git_command().args(["reset", "--hard"])).await?;
+        process::run(self.git_command().args(["clean", "-fd"])).await?;
         Ok(())
     }
 
@@ -50,3 +131,87 @@ impl Default for Git {
         Self::new()
     }
 }
+
+/// Returns `true` if `rev` is a full (SHA-1) commit hash rather than a ref name.
+pub fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[tokio::test]
+async fn test_checkout_verified_and_clean() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let git = Git::in_working_dir(dir);
+    git.init().await.unwrap();
+    assert!(git.is_repo_root().await);
+    process::run(git.git_command().args([
+        "-c",
+        "user.name=test",
+        "-c",
+        "user.email=test@example.com",
+        "commit",
+        "--allow-empty",
+        "-m",
+        "initial",
+    ]))
+    .await
+    .unwrap();
+
+    let head = git.rev_parse("HEAD").await.unwrap();
+    assert!(is_commit_hash(&head));
+    git.checkout_verified(&head).await.unwrap();
+    let missing = "0".repeat(40);
+    assert!(git.checkout_verified(&missing).await.is_err());
+
+    tokio::fs::write(dir.join("untracked.txt"), "x")
+        .await
+        .unwrap();
+    assert!(git.is_dirty().await.unwrap());
+    git.clean().await.unwrap();
+    assert!(!git.is_dirty().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_fetch_commit_from_mirror() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let dir = temp_dir.path();
+    let mirror_dir = dir.join("mirror");
+    tokio::fs::create_dir_all(&mirror_dir).await.unwrap();
+    let mirror = Git::in_working_dir(&mirror_dir);
+    mirror.init().await.unwrap();
+    let commit = || {
+        let mut command = mirror.git_command();
+        command.args([
+            "-c",
+            "user.name=test",
+            "-c",
+            "user.email=test@example.com",
+            "commit",
+            "--allow-empty",
+            "-m",
+            "test",
+        ]);
+        command
+    };
+    process::run(&mut commit()).await.unwrap();
+    // A commit on another branch, which is not reachable from the mirror's HEAD.
+    process::run(mirror.git_command().args(["checkout", "-b", "side"]))
+        .await
+        .unwrap();
+    process::run(&mut commit()).await.unwrap();
+    let side = mirror.rev_parse("HEAD").await.unwrap();
+    process::run(mirror.git_command().args(["checkout", "-"]))
+        .await
+        .unwrap();
+
+    let clone_dir = dir.join("clone");
+    tokio::fs::create_dir_all(&clone_dir).await.unwrap();
+    let git = Git::in_working_dir(&clone_dir);
+    git.init().await.unwrap();
+    let mirror_path = mirror_dir.to_string_lossy();
+    git.fetch(&mirror_path).await.unwrap();
+    assert!(!git.has_commit(&side).await);
+    git.fetch_refspecs(&mirror_path, &[&side]).await.unwrap();
+    assert!(git.has_commit(&side).await);
+    git.checkout_verified(&side).await.unwrap();
+}
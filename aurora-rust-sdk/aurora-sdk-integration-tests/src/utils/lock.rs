@@ -0,0 +1,56 @@
+//! Locks which are shared by all the processes on the machine (e.g. multiple test binaries
+//! run by `cargo test` in parallel), backed by an exclusive lock on a file.
+
+use fs2::FileExt;
+use std::{
+    fs::{File, OpenOptions},
+    path::{Path, PathBuf},
+};
+
+/// Holds an exclusive lock on a file until dropped. Locks on the same path also exclude
+/// each other within a single process.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl FileLock {
+    /// Waits until the lock on the file at `path` is acquired. The file (and its parent
+    /// directories) are created if necessary.
+    pub async fn acquire<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        // Locking blocks the thread, so it must not happen on the async runtime.
+        let lock = tokio::task::spawn_blocking(move || -> std::io::Result<Self> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(false)
+                .open(&path)?;
+            file.lock_exclusive()?;
+            Ok(Self { file, path })
+        })
+        .await??;
+        Ok(lock)
+    }
+
+    /// A lock in the system temporary directory, for resources which have no natural
+    /// location for a lock file. The name must be a valid file name.
+    pub async fn acquire_named(name: &str) -> anyhow::Result<Self> {
+        Self::acquire(std::env::temp_dir().join(format!("aurora-sdk-{name}.lock"))).await
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // The lock is released when the file is closed anyway, so errors can be ignored.
+        let _ = self.file.unlock();
+    }
+}
@@ -1,5 +1,8 @@
+pub mod artifact;
 pub mod cargo;
 pub mod ethabi;
 pub mod forge;
 pub mod git;
+pub mod lock;
 pub mod process;
+pub mod solc;
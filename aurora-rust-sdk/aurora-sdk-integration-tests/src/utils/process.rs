@@ -1,9 +1,303 @@
-use std::process::Output;
+use std::{
+    fmt,
+    path::PathBuf,
+    process::{ExitStatus, Output, Stdio},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader},
+    process::Command,
+};
+
+/// If set (to any value), the output of all commands run by this crate is printed as it
+/// is produced, which makes it visible in the test log.
+const STREAM_OUTPUT_ENV_VAR: &str = "AURORA_SDK_STREAM_COMMAND_OUTPUT";
+/// If set, the default timeout (in seconds) for all commands run by this crate.
+const TIMEOUT_ENV_VAR: &str = "AURORA_SDK_COMMAND_TIMEOUT_SECS";
 
 pub fn require_success(output: &Output) -> Result<(), anyhow::Error> {
     if output.status.success() {
         Ok(())
     } else {
-        Err(anyhow::Error::msg(format!("Command failed: {:?}", output)))
+        Err(anyhow::Error::msg(format!(
+            "Command failed with {}\nstdout:\n{}\nstderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        )))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandOptions {
+    /// The command is killed if it does not finish within this time.
+    pub timeout: Option<Duration>,
+    /// Print the output of the command line by line as it is produced.
+    pub stream_output: bool,
+}
+
+impl CommandOptions {
+    /// Options taken from the `AURORA_SDK_STREAM_COMMAND_OUTPUT` and
+    /// `AURORA_SDK_COMMAND_TIMEOUT_SECS` environment variables.
+    pub fn from_env() -> Self {
+        Self {
+            timeout: std::env::var(TIMEOUT_ENV_VAR)
+                .ok()
+                .and_then(|secs| secs.parse().ok())
+                .map(Duration::from_secs),
+            stream_output: std::env::var_os(STREAM_OUTPUT_ENV_VAR).is_some(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CommandErrorKind {
+    /// The command could not be started (e.g. the program is not installed).
+    Spawn(std::io::Error),
+    /// An IO error occurred while communicating with the running command.
+    Io(std::io::Error),
+    /// The command exited unsuccessfully.
+    Failed(ExitStatus),
+    TimedOut(Duration),
+}
+
+/// A command run by one of the helpers in this crate did not succeed.
+#[derive(Debug)]
+pub struct CommandError {
+    pub program: String,
+    pub args: Vec<String>,
+    pub working_dir: Option<PathBuf>,
+    pub kind: CommandErrorKind,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandError {
+    fn new(command: &Command, kind: CommandErrorKind, stdout: &[u8], stderr: &[u8]) -> Self {
+        let command = command.as_std();
+        Self {
+            program: command.get_program().to_string_lossy().into_owned(),
+            args: command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            working_dir: command.get_current_dir().map(Into::into),
+            kind,
+            stdout: String::from_utf8_lossy(stdout).into_owned(),
+            stderr: String::from_utf8_lossy(stderr).into_owned(),
+        }
+    }
+
+    /// The exit status of the command, if it ran to completion.
+    pub fn status(&self) -> Option<ExitStatus> {
+        match self.kind {
+            CommandErrorKind::Failed(status) => Some(status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Command `{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {arg}")?;
+        }
+        write!(f, "`")?;
+        if let Some(dir) = &self.working_dir {
+            write!(f, " (in {})", dir.display())?;
+        }
+        match &self.kind {
+            CommandErrorKind::Spawn(e) => write!(f, " could not be started: {e}")?,
+            CommandErrorKind::Io(e) => write!(f, " failed: {e}")?,
+            CommandErrorKind::Failed(status) => write!(f, " failed with {status}")?,
+            CommandErrorKind::TimedOut(timeout) => write!(f, " timed out after {timeout:?}")?,
+        }
+        if !self.stdout.is_empty() {
+            write!(f, "\nstdout:\n{}", self.stdout.trim_end())?;
+        }
+        if !self.stderr.is_empty() {
+            write!(f, "\nstderr:\n{}", self.stderr.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CommandError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            CommandErrorKind::Spawn(e) | CommandErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
     }
 }
+
+/// Runs the command to completion with the options from the environment
+/// (see `CommandOptions::from_env`) and returns its output if it exited successfully.
+pub async fn run(command: &mut Command) -> Result<Output, CommandError> {
+    run_with_options(command, &CommandOptions::from_env()).await
+}
+
+pub async fn run_with_options(
+    command: &mut Command,
+    options: &CommandOptions,
+) -> Result<Output, CommandError> {
+    run_with_input(command, &[], options).await
+}
+
+/// Runs the command with the given bytes written to its standard input.
+pub async fn run_with_input(
+    command: &mut Command,
+    input: &[u8],
+    options: &CommandOptions,
+) -> Result<Output, CommandError> {
+    // The buffers are owned here so that the output produced before a timeout or an IO
+    // error is kept in the `CommandError`.
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let execution = execute(
+        command,
+        input,
+        options.stream_output,
+        &mut stdout,
+        &mut stderr,
+    );
+    let result = match options.timeout {
+        Some(timeout) => tokio::time::timeout(timeout, execution)
+            .await
+            .unwrap_or(Err(CommandErrorKind::TimedOut(timeout))),
+        None => execution.await,
+    };
+    match result {
+        Ok(status) if status.success() => Ok(Output {
+            status,
+            stdout,
+            stderr,
+        }),
+        Ok(status) => Err(CommandError::new(
+            command,
+            CommandErrorKind::Failed(status),
+            &stdout,
+            &stderr,
+        )),
+        Err(kind) => Err(CommandError::new(command, kind, &stdout, &stderr)),
+    }
+}
+
+async fn execute(
+    command: &mut Command,
+    input: &[u8],
+    stream_output: bool,
+    stdout: &mut Vec<u8>,
+    stderr: &mut Vec<u8>,
+) -> Result<ExitStatus, CommandErrorKind> {
+    // Dropping the future on timeout drops the child, which then must not outlive it.
+    let mut child = command
+        .stdin(if input.is_empty() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(CommandErrorKind::Spawn)?;
+
+    // The input is written while the output is read: a command which fills its output pipe
+    // before reading all of its input would otherwise never finish.
+    let stdin = child.stdin.take();
+    let write_input = async move {
+        if let Some(mut stdin) = stdin {
+            match stdin.write_all(input).await {
+                // The command exited (or closed its input) without reading all of it. Its
+                // exit status tells whether that is an error.
+                Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {}
+                result => result?,
+            }
+            // Dropping stdin closes it, so that the command knows the input is complete.
+        }
+        Ok(())
+    };
+    // Unwraps are safe because stdout and stderr were configured as piped above.
+    let child_stdout = child.stdout.take().unwrap();
+    let child_stderr = child.stderr.take().unwrap();
+    let (input_result, stdout_result, stderr_result, status) = tokio::join!(
+        write_input,
+        read_stream(child_stdout, stream_output.then_some("stdout"), stdout),
+        read_stream(child_stderr, stream_output.then_some("stderr"), stderr),
+        child.wait(),
+    );
+
+    input_result.map_err(CommandErrorKind::Io)?;
+    stdout_result.map_err(CommandErrorKind::Io)?;
+    stderr_result.map_err(CommandErrorKind::Io)?;
+    status.map_err(CommandErrorKind::Io)
+}
+
+/// Reads the stream to the end into `result`, printing each line with the given label if
+/// one is given.
+async fn read_stream<R: AsyncRead + Unpin>(
+    stream: R,
+    stream_label: Option<&str>,
+    result: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let start = result.len();
+        if reader.read_until(b'\n', result).await? == 0 {
+            return Ok(());
+        }
+        if let Some(label) = stream_label {
+            let line = String::from_utf8_lossy(&result[start..]);
+            println!("[{label}] {}", line.trim_end());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_command_error() {
+    let err = run_with_options(
+        Command::new("sh").args(["-c", "echo out; echo err >&2; exit 3"]),
+        &CommandOptions::default(),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.program, "sh");
+    assert_eq!(err.status().and_then(|s| s.code()), Some(3));
+    assert_eq!(err.stdout, "out\n");
+    assert_eq!(err.stderr, "err\n");
+
+    let options = CommandOptions {
+        timeout: Some(Duration::from_millis(100)),
+        stream_output: true,
+    };
+    let err = run_with_options(Command::new("sleep").arg("10"), &options)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, CommandErrorKind::TimedOut(_)));
+
+    // The output produced before the timeout is kept.
+    let options = CommandOptions {
+        timeout: Some(Duration::from_millis(500)),
+        stream_output: false,
+    };
+    let err = run_with_options(
+        Command::new("sh").args(["-c", "echo started; sleep 10"]),
+        &options,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err.kind, CommandErrorKind::TimedOut(_)));
+    assert_eq!(err.stdout, "started\n");
+}
+
+#[tokio::test]
+async fn test_run_with_large_input() {
+    // Larger than the pipe buffers, so `cat` blocks on its output until it is read.
+    let input = vec![b'a'; 1 << 20];
+    let output = run_with_input(&mut Command::new("cat"), &input, &CommandOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(output.stdout, input);
+}
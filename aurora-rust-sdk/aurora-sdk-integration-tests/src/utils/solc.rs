@@ -0,0 +1,275 @@
+//! Compiles Solidity sources with a local `solc` binary, for environments where Foundry
+//! is not available. The compiler is invoked with standard-JSON input; the `SOLC`
+//! environment variable can be used to select the binary (defaults to `solc` from `PATH`).
+
+use crate::utils::{
+    artifact::SolidityArtifact,
+    ethabi::ContractConstructor,
+    process::{self, CommandOptions},
+};
+use aurora_engine_types::types::Address;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+
+const SOLC_ENV_VAR: &str = "SOLC";
+const DEFAULT_SOLC: &str = "solc";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolcSettings {
+    /// Import remappings, e.g. `aurora-sdk/=lib/aurora-solidity-sdk/src/`.
+    pub remappings: Vec<String>,
+    /// Number of optimizer runs; the optimizer is disabled if `None`.
+    pub optimizer_runs: Option<u32>,
+    /// Target EVM version (e.g. `london`); the compiler default is used if `None`.
+    pub evm_version: Option<String>,
+    /// Source file -> library name -> address of already deployed libraries.
+    pub libraries: BTreeMap<String, BTreeMap<String, Address>>,
+}
+
+/// The result of a successful compilation.
+#[derive(Debug, Clone)]
+pub struct SolcOutput {
+    /// Fully qualified contract name (`path/to/File.sol:Contract`) -> artifact.
+    pub contracts: BTreeMap<String, SolidityArtifact>,
+    /// Diagnostics which did not prevent the compilation (e.g. warnings).
+    pub warnings: Vec<SolcDiagnostic>,
+}
+
+impl SolcOutput {
+    /// Converts all the contracts into `ContractConstructor`s. Fails if any of the
+    /// contracts has unlinked libraries.
+    pub fn into_constructors(self) -> anyhow::Result<BTreeMap<String, ContractConstructor>> {
+        let mut result = BTreeMap::new();
+        for (name, artifact) in self.contracts {
+            let constructor = artifact
+                .into_constructor()
+                .map_err(|e| anyhow::Error::new(e).context(format!("Contract {name}")))?;
+            result.insert(name, constructor);
+        }
+        Ok(result)
+    }
+
+    /// Finds a contract by its fully qualified name or just by its name.
+    pub fn contract(&self, name: &str) -> Option<&SolidityArtifact> {
+        self.contracts.get(name).or_else(|| {
+            self.contracts
+                .iter()
+                .find(|(qualified, _)| qualified.rsplit(':').next() == Some(name))
+                .map(|(_, artifact)| artifact)
+        })
+    }
+}
+
+/// An error, warning or info message reported by the compiler.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolcDiagnostic {
+    pub severity: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+    pub formatted_message: Option<String>,
+    pub error_code: Option<String>,
+    pub source_location: Option<SourceLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+impl SolcDiagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+impl fmt::Display for SolcDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.formatted_message {
+            Some(formatted) => write!(f, "{}", formatted.trim_end()),
+            None => write!(f, "{}: {}", self.kind, self.message),
+        }
+    }
+}
+
+/// The compiler reported errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolcError {
+    pub diagnostics: Vec<SolcDiagnostic>,
+}
+
+impl SolcError {
+    pub fn errors(&self) -> impl Iterator<Item = &SolcDiagnostic> {
+        self.diagnostics.iter().filter(|d| d.is_error())
+    }
+}
+
+impl fmt::Display for SolcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Solidity compilation failed:")?;
+        for diagnostic in self.errors() {
+            write!(f, "\n{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SolcError {}
+
+/// Compiles the given sources (paths relative to `root_path`) with default settings and
+/// returns a `ContractConstructor` for every contract, keyed by fully qualified name.
+pub async fn compile<P: AsRef<Path>>(
+    root_path: P,
+    sources: &[&str],
+) -> anyhow::Result<BTreeMap<String, ContractConstructor>> {
+    compile_with_settings(root_path, sources, &SolcSettings::default())
+        .await?
+        .into_constructors()
+}
+
+/// Compiles the given sources (paths relative to `root_path`). Imports are resolved
+/// relative to `root_path` and through the remappings in the settings. The returned
+/// artifacts may still reference libraries which are not included in `settings.libraries`;
+/// these can be linked with `SolidityArtifact::link_library`.
+pub async fn compile_with_settings<P: AsRef<Path>>(
+    root_path: P,
+    sources: &[&str],
+    settings: &SolcSettings,
+) -> anyhow::Result<SolcOutput> {
+    let root_path = root_path.as_ref();
+    let input = standard_json_input(root_path, sources, settings).await?;
+    let solc: PathBuf = std::env::var_os(SOLC_ENV_VAR)
+        .map(Into::into)
+        .unwrap_or_else(|| DEFAULT_SOLC.into());
+
+    // solc reports compilation errors in its JSON output and exits successfully, so a
+    // failure here means the input itself could not be processed.
+    let output = process::run_with_input(
+        Command::new(solc).current_dir(root_path).args([
+            "--standard-json",
+            "--base-path",
+            ".",
+            "--allow-paths",
+            ".",
+        ]),
+        &serde_json::to_vec(&input)?,
+        &CommandOptions::from_env(),
+    )
+    .await?;
+
+    let output: Value = serde_json::from_slice(&output.stdout)?;
+    parse_standard_json_output(&output)
+}
+
+async fn standard_json_input(
+    root_path: &Path,
+    sources: &[&str],
+    settings: &SolcSettings,
+) -> anyhow::Result<Value> {
+    let mut source_map = serde_json::Map::new();
+    for source in sources {
+        let content = tokio::fs::read_to_string(root_path.join(source))
+            .await
+            .map_err(|e| anyhow::Error::new(e).context(format!("Failed to read {source}")))?;
+        source_map.insert(source.to_string(), json!({ "content": content }));
+    }
+
+    let libraries: BTreeMap<&String, BTreeMap<&String, String>> = settings
+        .libraries
+        .iter()
+        .map(|(file, libraries)| {
+            let addresses = libraries
+                .iter()
+                .map(|(name, address)| (name, format!("0x{}", address.encode())))
+                .collect();
+            (file, addresses)
+        })
+        .collect();
+    let mut solc_settings = json!({
+        "remappings": settings.remappings,
+        "optimizer": {
+            "enabled": settings.optimizer_runs.is_some(),
+            "runs": settings.optimizer_runs.unwrap_or(200),
+        },
+        "libraries": libraries,
+        "outputSelection": {
+            "*": {
+                "*": ["abi", "evm.bytecode", "evm.deployedBytecode", "evm.methodIdentifiers"],
+            },
+        },
+    });
+    if let Some(evm_version) = &settings.evm_version {
+        solc_settings["evmVersion"] = json!(evm_version);
+    }
+
+    Ok(json!({
+        "language": "Solidity",
+        "sources": source_map,
+        "settings": solc_settings,
+    }))
+}
+
+fn parse_standard_json_output(output: &Value) -> anyhow::Result<SolcOutput> {
+    let diagnostics: Vec<SolcDiagnostic> = match output.get("errors") {
+        Some(errors) => serde_json::from_value(errors.clone())?,
+        None => Vec::new(),
+    };
+    if diagnostics.iter().any(SolcDiagnostic::is_error) {
+        return Err(SolcError { diagnostics }.into());
+    }
+
+    let mut contracts = BTreeMap::new();
+    let files = output.get("contracts").and_then(Value::as_object);
+    for (file, file_contracts) in files.into_iter().flatten() {
+        for (name, contract) in file_contracts.as_object().into_iter().flatten() {
+            let artifact = SolidityArtifact::from_json(contract, Some(name.as_str()))?;
+            contracts.insert(format!("{file}:{name}"), artifact);
+        }
+    }
+
+    Ok(SolcOutput {
+        contracts,
+        warnings: diagnostics,
+    })
+}
+
+#[test]
+fn test_parse_solc_diagnostics() {
+    let output = json!({
+        "errors": [
+            {
+                "severity": "warning", "type": "Warning", "component": "general",
+                "message": "Unused local variable.", "errorCode": "2072",
+                "sourceLocation": {"file": "src/A.sol", "start": 10, "end": 20},
+            },
+            {
+                "severity": "error", "type": "TypeError", "component": "general",
+                "message": "Undeclared identifier.", "errorCode": "7576",
+                "formattedMessage": "TypeError: Undeclared identifier.\n --> src/A.sol:3:5:\n",
+            },
+        ],
+    });
+    let err = parse_standard_json_output(&output).unwrap_err();
+    let err = err.downcast::<SolcError>().unwrap();
+    assert_eq!(err.errors().count(), 1);
+    assert_eq!(
+        err.to_string(),
+        "Solidity compilation failed:\nTypeError: Undeclared identifier.\n --> src/A.sol:3:5:"
+    );
+    assert_eq!(
+        err.diagnostics[0].source_location,
+        Some(SourceLocation {
+            file: "src/A.sol".into(),
+            start: 10,
+            end: 20,
+        })
+    );
+}
@@ -1,10 +1,22 @@
 use crate::{
-    aurora_engine::{erc20::ERC20, AuroraEngine},
-    nep141::{self, AccountIdArgs},
+    aurora_engine::{erc20::ERC20, AuroraEngine, CallOutcome, NearGasOutcome},
+    nep141::{self, AccountIdArgs, Nep141},
 };
-use workspaces::{network::Sandbox, types::NearToken, Contract, Worker};
+use aurora_engine_types::{types::Address, U256};
+use workspaces::{network::Sandbox, types::NearToken, AccountId, Contract, Worker};
 
 const STORAGE_DEPOSIT_AMOUNT: u128 = 1_000_000_000_000_000_000_000_000;
+const ONE_YOCTO: NearToken = NearToken::from_yoctonear(1);
+
+/// The outcome of `Wnear::deposit_to_evm`.
+#[derive(Debug, Clone)]
+pub struct WnearDepositOutcome {
+    /// The `near_deposit` which wraps the account's NEAR (not including the registration of
+    /// the account, if needed).
+    pub deposit: NearGasOutcome,
+    /// The `ft_transfer_call` which sends the wNEAR to the engine.
+    pub transfer: NearGasOutcome,
+}
 
 pub struct Wnear {
     pub inner: Contract,
@@ -47,34 +59,186 @@ impl Wnear {
         Ok(result)
     }
 
-    pub async fn storage_deposit(&self, account: &workspaces::Account) -> anyhow::Result<()> {
-        account
+    pub async fn storage_deposit(
+        &self,
+        account: &workspaces::Account,
+    ) -> anyhow::Result<NearGasOutcome> {
+        let outcome = account
             .call(self.inner.id(), "storage_deposit")
             .args_json(AccountIdArgs {
                 account_id: account.id(),
             })
             .deposit(NearToken::from_yoctonear(STORAGE_DEPOSIT_AMOUNT))
             .transact()
-            .await?
-            .into_result()?;
-        Ok(())
+            .await?;
+        NearGasOutcome::from_successful_execution(outcome)
     }
 
     pub async fn near_deposit(
         &self,
         account: &workspaces::Account,
         amount: u128,
-    ) -> anyhow::Result<()> {
-        account
+    ) -> anyhow::Result<NearGasOutcome> {
+        let outcome = account
             .call(self.inner.id(), "near_deposit")
             .deposit(NearToken::from_yoctonear(amount))
             .transact()
-            .await?
-            .into_result()?;
-        Ok(())
+            .await?;
+        NearGasOutcome::from_successful_execution(outcome)
     }
 
     pub async fn ft_balance_of(&self, account_id: &workspaces::AccountId) -> anyhow::Result<u128> {
         nep141::ft_balance_of(self.inner.as_account(), self.inner.id(), account_id).await
     }
+
+    /// Unwraps `amount` wNEAR of the account back to NEAR. Checks that the wNEAR was burned
+    /// and that the native NEAR balance increased by `amount`, less the gas paid.
+    pub async fn near_withdraw(
+        &self,
+        account: &workspaces::Account,
+        amount: u128,
+    ) -> anyhow::Result<NearGasOutcome> {
+        let balance_before = self.ft_balance_of(account.id()).await?;
+        let near_before = account.view_account().await?.balance.as_yoctonear();
+        let outcome = account
+            .call(self.inner.id(), "near_withdraw")
+            .args_json(serde_json::json!({ "amount": amount.to_string() }))
+            .deposit(ONE_YOCTO)
+            .transact()
+            .await?;
+        let near = NearGasOutcome::from_successful_execution(outcome)?;
+        let balance_after = self.ft_balance_of(account.id()).await?;
+        let near_after = account.view_account().await?.balance.as_yoctonear();
+        if balance_before.checked_sub(balance_after) != Some(amount)
+            || !near.signer_received(near_before, near_after, amount, ONE_YOCTO.as_yoctonear())
+        {
+            return Err(anyhow::Error::msg(format!(
+                "near_withdraw of {amount} wNEAR failed: the balance changed from \
+                 {balance_before} to {balance_after} and the NEAR balance from {near_before} \
+                 to {near_after}"
+            )));
+        }
+        Ok(near)
+    }
+
+    pub async fn ft_transfer(
+        &self,
+        sender: &workspaces::Account,
+        receiver_id: &AccountId,
+        amount: u128,
+    ) -> anyhow::Result<NearGasOutcome> {
+        let balance_before = self.ft_balance_of(receiver_id).await?;
+        let near = self
+            .as_nep141()
+            .ft_transfer(sender, receiver_id, amount, None)
+            .await?;
+        let balance_after = self.ft_balance_of(receiver_id).await?;
+        check_balance_change("ft_transfer", balance_before, balance_after, amount)?;
+        Ok(near)
+    }
+
+    /// Returns the amount which the receiver used, i.e. the amount which was not refunded.
+    pub async fn ft_transfer_call(
+        &self,
+        sender: &workspaces::Account,
+        receiver_id: &AccountId,
+        amount: u128,
+        msg: &str,
+    ) -> anyhow::Result<u128> {
+        let (used_amount, _) = self
+            .ft_transfer_call_outcome(sender, receiver_id, amount, msg)
+            .await?;
+        Ok(used_amount)
+    }
+
+    /// Same as `ft_transfer_call`, but also returns the NEAR gas burnt.
+    pub async fn ft_transfer_call_outcome(
+        &self,
+        sender: &workspaces::Account,
+        receiver_id: &AccountId,
+        amount: u128,
+        msg: &str,
+    ) -> anyhow::Result<(u128, NearGasOutcome)> {
+        let balance_before = self.ft_balance_of(receiver_id).await?;
+        let (used_amount, near) = self
+            .as_nep141()
+            .ft_transfer_call_outcome(sender, receiver_id, amount, msg)
+            .await?;
+        let balance_after = self.ft_balance_of(receiver_id).await?;
+        check_balance_change(
+            "ft_transfer_call",
+            balance_before,
+            balance_after,
+            used_amount,
+        )?;
+        Ok((used_amount, near))
+    }
+
+    /// Wraps `amount` NEAR of the account and sends the wNEAR to `recipient` in the EVM (as
+    /// the wNEAR ERC-20 token of the engine).
+    pub async fn deposit_to_evm(
+        &self,
+        engine: &AuroraEngine,
+        account: &workspaces::Account,
+        recipient: Address,
+        amount: u128,
+    ) -> anyhow::Result<WnearDepositOutcome> {
+        let nep141 = self.as_nep141();
+        if nep141.storage_balance_of(account.id()).await?.is_none() {
+            self.storage_deposit(account).await?;
+        }
+        let deposit = self.near_deposit(account, amount).await?;
+        let balance_before = engine
+            .erc20_balance_of(&self.aurora_token, recipient)
+            .await?;
+        let (used_amount, transfer) = nep141
+            .ft_transfer_call_outcome(account, engine.inner.id(), amount, &recipient.encode())
+            .await?;
+        let balance_after = engine
+            .erc20_balance_of(&self.aurora_token, recipient)
+            .await?;
+        if used_amount != amount
+            || balance_after.checked_sub(balance_before) != Some(U256::from(amount))
+        {
+            return Err(anyhow::Error::msg(format!(
+                "Deposit of {amount} wNEAR to {} failed: the ERC-20 balance changed from \
+                 {balance_before} to {balance_after}",
+                recipient.encode()
+            )));
+        }
+        Ok(WnearDepositOutcome { deposit, transfer })
+    }
+
+    /// Withdraws `amount` wNEAR from the EVM address of `account` and unwraps it, so that
+    /// `recipient` receives native NEAR. See `AuroraEngine::unwrap_wnear_with`.
+    pub async fn unwrap_from_evm(
+        &self,
+        engine: &AuroraEngine,
+        account: &workspaces::Account,
+        recipient: &workspaces::Account,
+        amount: u128,
+    ) -> anyhow::Result<CallOutcome> {
+        engine
+            .unwrap_wnear_with(account, self, recipient, amount)
+            .await
+    }
+
+    fn as_nep141(&self) -> Nep141 {
+        Nep141::at(self.inner.clone())
+    }
+}
+
+/// Checks that a wNEAR balance increased by `amount` from `before` to `after`.
+fn check_balance_change(
+    method: &str,
+    before: u128,
+    after: u128,
+    amount: u128,
+) -> anyhow::Result<()> {
+    if after.checked_sub(before) != Some(amount) {
+        return Err(anyhow::Error::msg(format!(
+            "{method} of {amount} wNEAR failed: the balance changed from {before} to {after}"
+        )));
+    }
+    Ok(())
 }
@@ -0,0 +1,29 @@
+//! Tests of the `#[aurora_test]` attribute macro. Unlike the other tests (see `src/lib.rs`),
+//! these are a separate crate because the macro expands to paths through
+//! `::aurora_sdk_integration_tests`, which only resolve outside of this crate.
+
+use aurora_sdk_integration_tests::{
+    aurora_test,
+    fixture::{AuroraFixture, DEFAULT_WNEAR_BALANCE},
+};
+
+#[aurora_test(users = ["alice", "bob"])]
+async fn test_aurora_test_with_fixture(fixture: AuroraFixture) {
+    let alice = fixture.user("alice");
+    let bob = fixture.user("bob");
+    assert_ne!(alice.address, bob.address);
+    let balance = fixture
+        .engine
+        .erc20_balance_of(&fixture.wnear.aurora_token, alice.address)
+        .await
+        .unwrap();
+    assert_eq!(balance, DEFAULT_WNEAR_BALANCE.into());
+}
+
+#[aurora_test]
+async fn test_aurora_test_without_fixture() -> anyhow::Result<()> {
+    let worker = aurora_sdk_integration_tests::workspaces::sandbox().await?;
+    let account = worker.dev_create_account().await?;
+    assert!(worker.view_account(account.id()).await.is_ok());
+    Ok(())
+}
@@ -77,26 +77,29 @@ library AuroraSdk {
     }
 
     /// Get the promise result at the specified index.
-    function promiseResult(uint256 index) public returns (PromiseResult memory result) {
+    function promiseResult(uint256 index) public returns (PromiseResult memory) {
         (bool success, bytes memory returnData) = PROMISE_RESULT_PRECOMPILE.call("");
         require(success);
+        return decodePromiseResult(returnData, index);
+    }
 
-        Borsh.Data memory borsh = Borsh.from(returnData);
+    /// Decode the promise result at the specified index from the output of the promise
+    /// result precompile, a borsh-serialized list of promise results.
+    function decodePromiseResult(bytes memory data, uint256 index)
+        internal
+        pure
+        returns (PromiseResult memory result)
+    {
+        Borsh.Data memory borsh = Borsh.from(data);
 
         uint32 length = borsh.decodeU32();
         require(index < length, "Index out of bounds");
 
         for (uint256 i = 0; i < index; i++) {
-            PromiseResultStatus status = PromiseResultStatus(uint8(borsh.decodeU8()));
-            if (status == PromiseResultStatus.Successful) {
-                borsh.skipBytes();
-            }
+            borsh.skipPromiseResult();
         }
 
-        result.status = PromiseResultStatus(borsh.decodeU8());
-        if (result.status == PromiseResultStatus.Successful) {
-            result.output = borsh.decodeBytes();
-        }
+        result = borsh.decodePromiseResult();
     }
 
     /// Get the NEAR account id of the current contract. It is the account id of Aurora engine.
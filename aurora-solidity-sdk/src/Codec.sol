@@ -80,8 +80,9 @@ library Codec {
         return abi.encodePacked(encode(mode), encode(PromiseArgsVariant.Callback), encode(nearPromise));
     }
 
-    /// Decode promise result using borsh.
-    function decodePromiseResult(Borsh.Data memory data) public pure returns (PromiseResult memory result) {
+    /// Decode promise result using borsh. This is internal because `Borsh.Data` points into
+    /// the caller's memory, which a (delegate) call to the deployed library does not share.
+    function decodePromiseResult(Borsh.Data memory data) internal pure returns (PromiseResult memory result) {
         result.status = PromiseResultStatus(data.decodeU8());
         if (result.status == PromiseResultStatus.Successful) {
             result.output = data.decodeBytes();
@@ -89,7 +90,7 @@ library Codec {
     }
 
     /// Skip promise result from the buffer.
-    function skipPromiseResult(Borsh.Data memory data) public pure {
+    function skipPromiseResult(Borsh.Data memory data) internal pure {
         PromiseResultStatus status = PromiseResultStatus(uint8(data.decodeU8()));
         if (status == PromiseResultStatus.Successful) {
             data.skipBytes();
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: CC-BY-1.0
+pragma solidity ^0.8.17;
+
+import "openzeppelin-contracts/token/ERC20/IERC20.sol";
+import "../src/AuroraSdk.sol";
+
+/// Exposes the entry points of `AuroraSdk` so that they can be called from the Rust
+/// integration tests (`aurora-sdk-integration-tests`) against a sandbox engine. Callbacks
+/// record what they observed, to be checked afterwards with view calls.
+///
+/// Promises created with `call` are paid for in wNEAR by the caller, who must approve
+/// this contract to spend it. `transactRaw` pays nothing, see its documentation.
+contract AuroraSdkTestHelper {
+    using AuroraSdk for NEAR;
+    using AuroraSdk for PromiseCreateArgs;
+    using AuroraSdk for PromiseWithCallback;
+
+    NEAR public near;
+
+    /// Predecessor account ID seen by the last `recordPredecessor` call.
+    string public lastPredecessor;
+
+    /// Promise results seen by the last `recordPromiseResults` call.
+    PromiseResult[] private results;
+
+    constructor(IERC20 wNEAR) {
+        near = AuroraSdk.initNear(wNEAR);
+    }
+
+    function currentAccountId() external returns (string memory) {
+        return AuroraSdk.currentAccountId();
+    }
+
+    function predecessorAccountId() external returns (string memory) {
+        return AuroraSdk.predecessorAccountId();
+    }
+
+    function nearRepresentative(address account) external returns (string memory) {
+        return AuroraSdk.nearRepresentative(account);
+    }
+
+    function addressSubAccount(address account, string memory accountId) external pure returns (string memory) {
+        return AuroraSdk.addressSubAccount(account, accountId);
+    }
+
+    function implicitAuroraAddress(string memory accountId) external pure returns (address) {
+        return AuroraSdk.implicitAuroraAddress(accountId);
+    }
+
+    function nearRepresentitiveImplicitAddress(address account) external returns (address) {
+        return AuroraSdk.nearRepresentitiveImplicitAddress(account);
+    }
+
+    /// Calls `method` of `target` on NEAR.
+    function transactCall(
+        string memory target,
+        string memory method,
+        bytes memory args,
+        uint128 nearBalance,
+        uint64 nearGas
+    ) external {
+        near.call(target, method, args, nearBalance, nearGas).transact();
+    }
+
+    /// Calls `method` of `target` on NEAR, followed by `recordPromiseResults(1)`.
+    function transactCallWithCallback(
+        string memory target,
+        string memory method,
+        bytes memory args,
+        uint64 nearGas,
+        uint64 callbackGas
+    ) external {
+        PromiseCreateArgs memory base = near.call(target, method, args, 0, nearGas);
+        base.then(recordPromiseResultsCallback(1, callbackGas)).transact();
+    }
+
+    /// Schedules a call of `method` of `target` on NEAR, to be executed by the router's
+    /// `execute_scheduled`.
+    function lazyTransactCall(
+        string memory target,
+        string memory method,
+        bytes memory args,
+        uint128 nearBalance,
+        uint64 nearGas
+    ) external {
+        near.call(target, method, args, nearBalance, nearGas).lazy_transact();
+    }
+
+    /// Like `transactCallWithCallback`, but only schedules the promise.
+    function lazyTransactCallWithCallback(
+        string memory target,
+        string memory method,
+        bytes memory args,
+        uint64 nearGas,
+        uint64 callbackGas
+    ) external {
+        PromiseCreateArgs memory base = near.call(target, method, args, 0, nearGas);
+        base.then(recordPromiseResultsCallback(1, callbackGas)).lazy_transact();
+    }
+
+    /// Calls this contract with `args` through NEAR (i.e. from its XCC sub-account).
+    function transactAuroraCall(bytes memory args, uint64 nearGas) external {
+        near.auroraCall(address(this), args, 0, nearGas).transact();
+    }
+
+    /// Passes borsh-serialized `CrossContractCallArgs` to the XCC precompile as they are,
+    /// for promises which the SDK cannot create (e.g. joint promises). This contract holds
+    /// no wNEAR, so the XCC sub-account must already exist (see `transactCall`) and the
+    /// promises must not attach any NEAR.
+    function transactRaw(bytes memory crossContractCallArgs) external {
+        (bool success, bytes memory returnData) = XCC_PRECOMPILE.call(crossContractCallArgs);
+        if (!success) {
+            revert(string(returnData));
+        }
+    }
+
+    function recordPredecessor() external {
+        lastPredecessor = AuroraSdk.predecessorAccountId();
+    }
+
+    function recordPromiseResults(uint256 count) external {
+        delete results;
+        for (uint256 i = 0; i < count; i++) {
+            results.push(AuroraSdk.promiseResult(i));
+        }
+    }
+
+    function promiseResultCount() external view returns (uint256) {
+        return results.length;
+    }
+
+    function promiseResultAt(uint256 index) external view returns (PromiseResultStatus, bytes memory) {
+        PromiseResult storage result = results[index];
+        return (result.status, result.output);
+    }
+
+    function recordPromiseResultsCallback(uint256 count, uint64 callbackGas)
+        private
+        returns (PromiseCreateArgs memory)
+    {
+        return near.auroraCall(
+            address(this), abi.encodeWithSelector(this.recordPromiseResults.selector, count), 0, callbackGas
+        );
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: CC-BY-1.0
+pragma solidity ^0.8.17;
+
+import "../src/AuroraSdk.sol";
+import "../src/Codec.sol";
+import "../src/Types.sol";
+
+/// Exposes the borsh encoding of `Codec` and the promise result decoding of `AuroraSdk` so
+/// that the Rust integration tests (`aurora-sdk-integration-tests`) can compare them with the
+/// Rust types used by the engine.
+contract CodecWrapper {
+    using Codec for PromiseCreateArgs;
+    using Codec for PromiseWithCallback;
+
+    function encodePromiseCreateArgs(PromiseCreateArgs memory nearPromise) external pure returns (bytes memory) {
+        return nearPromise.encode();
+    }
+
+    function encodePromiseWithCallback(PromiseWithCallback memory nearPromise) external pure returns (bytes memory) {
+        return nearPromise.encode();
+    }
+
+    function encodeCrossContractCallArgs(PromiseCreateArgs memory nearPromise, ExecutionMode mode)
+        external
+        pure
+        returns (bytes memory)
+    {
+        return nearPromise.encodeCrossContractCallArgs(mode);
+    }
+
+    function encodeCrossContractCallArgsWithCallback(PromiseWithCallback memory nearPromise, ExecutionMode mode)
+        external
+        pure
+        returns (bytes memory)
+    {
+        return nearPromise.encodeCrossContractCallArgs(mode);
+    }
+
+    /// Decodes the promise result at `index` from a borsh-serialized `Vec<PromiseResult>`,
+    /// with the parsing `AuroraSdk.promiseResult` applies to the promise result precompile's
+    /// output.
+    function decodePromiseResult(bytes memory data, uint256 index)
+        external
+        pure
+        returns (PromiseResultStatus, bytes memory)
+    {
+        PromiseResult memory result = AuroraSdk.decodePromiseResult(data, index);
+        return (result.status, result.output);
+    }
+}
@@ -251,32 +251,20 @@ mod tests {
     ) -> SolidityContract<'a> {
         let contract_path = "../solidity-contract";
         let aurora_sdk_path = Path::new(contract_path).join("lib/aurora-solidity-sdk");
-        let codec_lib = forge::deploy_codec_lib(&aurora_sdk_path, engine)
+        let aurora_sdk = forge::AuroraSdkDeployment::deploy(&aurora_sdk_path, engine)
             .await
             .unwrap();
-        let utils_lib = forge::deploy_utils_lib(&aurora_sdk_path, engine)
+        let inner = aurora_sdk
+            .build_and_deploy_linked(
+                contract_path,
+                &["out", "FtRefund.sol", "FtRefund.json"],
+                &[
+                    ethabi::Token::String(contract_id.as_str().into()),
+                    ethabi::Token::Address(wnear_address.raw()),
+                ],
+            )
             .await
             .unwrap();
-        let aurora_sdk_lib =
-            forge::deploy_aurora_sdk_lib(&aurora_sdk_path, engine, codec_lib, utils_lib)
-                .await
-                .unwrap();
-        let solidity_constructor = forge::forge_build(
-            contract_path,
-            &[format!(
-                "aurora-sdk/AuroraSdk.sol:AuroraSdk:0x{}",
-                aurora_sdk_lib.encode()
-            )],
-            &["out", "FtRefund.sol", "FtRefund.json"],
-        )
-        .await
-        .unwrap();
-        let code = solidity_constructor.create_deploy_bytes_with_args(&[
-            ethabi::Token::String(contract_id.as_str().into()),
-            ethabi::Token::Address(wnear_address.raw()),
-        ]);
-        let contract_address = engine.deploy_evm_contract(code).await.unwrap();
-        let inner = solidity_constructor.deployed_at(contract_address);
 
         SolidityContract { inner, engine }
     }
@@ -174,35 +174,21 @@ mod tests {
     ) -> DeployedContract {
         let contract_path = "../contract";
         let aurora_sdk_path = Path::new(contract_path).join("lib/aurora-solidity-sdk");
-        let codec_lib = forge::deploy_codec_lib(&aurora_sdk_path, engine)
+        let aurora_sdk = forge::AuroraSdkDeployment::deploy(&aurora_sdk_path, engine)
             .await
             .unwrap();
-        let utils_lib = forge::deploy_utils_lib(&aurora_sdk_path, engine)
-            .await
-            .unwrap();
-        let aurora_sdk_lib =
-            forge::deploy_aurora_sdk_lib(&aurora_sdk_path, engine, codec_lib, utils_lib)
-                .await
-                .unwrap();
-        let constructor = forge::forge_build(
-            contract_path,
-            &[format!(
-                "aurora-sdk/AuroraSdk.sol:AuroraSdk:0x{}",
-                aurora_sdk_lib.encode()
-            )],
-            &["out", "SocialDB.sol", "SocialDB.json"],
-        )
-        .await
-        .unwrap();
-        let deploy_bytes = constructor.create_deploy_bytes_with_args(&[
-            ethabi::Token::String(social_db_account_id.to_string()),
-            ethabi::Token::Address(wnear_address.raw()),
-        ]);
-        let address = engine
-            .deploy_evm_contract_with(user_account, deploy_bytes)
+        aurora_sdk
+            .build_and_deploy_linked_with(
+                user_account,
+                contract_path,
+                &["out", "SocialDB.sol", "SocialDB.json"],
+                &[
+                    ethabi::Token::String(social_db_account_id.to_string()),
+                    ethabi::Token::Address(wnear_address.raw()),
+                ],
+            )
             .await
-            .unwrap();
-        constructor.deployed_at(address)
+            .unwrap()
     }
 
     async fn deploy_social_db(
@@ -87,18 +87,8 @@ mod tests {
             .unwrap();
         assert_eq!(state, 0x110101110101111100);
         // If user tries to take another turn they get a game over error
-        let err = solidity_contract
-            .take_turn(0x000000010000000000)
-            .await
-            .unwrap_err();
-        let err_msg = format!("{err:?}");
-        let expected_err_msg = format!("{:?}", b"Game Over")
-            .replace('[', "")
-            .replace(']', "");
-        assert!(
-            err_msg.contains(expected_err_msg.as_str()),
-            "Unexpected error: {err_msg}"
-        );
+        let result = solidity_contract.take_turn(0x000000010000000000).await;
+        aurora_engine::assert_reverted_with(result, "Game Over");
     }
 
     async fn deploy_solidity_tic_tac_toe<'a>(
@@ -109,32 +99,20 @@ mod tests {
     ) -> TicTacToeContract<'a> {
         let contract_path = "../solidity-contract";
         let aurora_sdk_path = Path::new(contract_path).join("lib/aurora-solidity-sdk");
-        let codec_lib = forge::deploy_codec_lib(&aurora_sdk_path, engine)
+        let aurora_sdk = forge::AuroraSdkDeployment::deploy(&aurora_sdk_path, engine)
             .await
             .unwrap();
-        let utils_lib = forge::deploy_utils_lib(&aurora_sdk_path, engine)
+        let inner = aurora_sdk
+            .build_and_deploy_linked(
+                contract_path,
+                &["out", "TicTacToe.sol", "TicTacToe.json"],
+                &[
+                    ethabi::Token::String(tic_tac_toe_account_id.as_str().into()),
+                    ethabi::Token::Address(wnear_address.raw()),
+                ],
+            )
             .await
             .unwrap();
-        let aurora_sdk_lib =
-            forge::deploy_aurora_sdk_lib(&aurora_sdk_path, engine, codec_lib, utils_lib)
-                .await
-                .unwrap();
-        let solidity_constructor = forge::forge_build(
-            contract_path,
-            &[format!(
-                "aurora-sdk/AuroraSdk.sol:AuroraSdk:0x{}",
-                aurora_sdk_lib.encode()
-            )],
-            &["out", "TicTacToe.sol", "TicTacToe.json"],
-        )
-        .await
-        .unwrap();
-        let code = solidity_constructor.create_deploy_bytes_with_args(&[
-            ethabi::Token::String(tic_tac_toe_account_id.as_str().into()),
-            ethabi::Token::Address(wnear_address.raw()),
-        ]);
-        let contract_address = engine.deploy_evm_contract(code).await.unwrap();
-        let inner = solidity_constructor.deployed_at(contract_address);
 
         // An account that represents a random Aurora user.
         // In reality this user does not need to have a NEAR account, they just need a way